@@ -0,0 +1,314 @@
+use std::collections::HashSet;
+
+use crate::versioning::{self, BumpRule, Increment};
+
+/// Changelog headings, in the order they are rendered within a release.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Section {
+    Breaking,
+    Features,
+    Fixes,
+    Performance,
+    Other,
+}
+
+impl Section {
+    /// Picks the section of a commit.
+    ///
+    /// Breaking changes and features are whatever `rules` bump as major and minor, so
+    /// custom rules group the changelog the same way they drive `--auto`. The remaining
+    /// commits are grouped by their conventional-commit type.
+    pub fn of(message: &str, rules: &[BumpRule]) -> Self {
+        match versioning::classify_commit(message, rules) {
+            Some(Increment::Major) => Section::Breaking,
+            Some(Increment::Minor) => Section::Features,
+            _ => match Subject::parse(message).kind {
+                Some("fix") => Section::Fixes,
+                Some("perf") => Section::Performance,
+                _ => Section::Other,
+            },
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Section::Breaking => "Breaking Changes",
+            Section::Features => "Features",
+            Section::Fixes => "Bug Fixes",
+            Section::Performance => "Performance",
+            Section::Other => "Other Changes",
+        }
+    }
+}
+
+/// The first line of a commit message split into its conventional-commit parts.
+/// A subject that doesn't follow the convention is kept whole as the description.
+struct Subject<'a> {
+    kind: Option<&'a str>,
+    scope: Option<&'a str>,
+    description: &'a str,
+}
+
+impl<'a> Subject<'a> {
+    fn parse(message: &'a str) -> Self {
+        let line = message.lines().next().unwrap_or_default().trim();
+        let plain = Self {
+            kind: None,
+            scope: None,
+            description: line,
+        };
+        let Some((head, description)) = line.split_once(": ") else {
+            return plain;
+        };
+        let head = head.strip_suffix('!').unwrap_or(head);
+        let (kind, scope) = match head.split_once('(') {
+            Some((kind, scope)) => match scope.strip_suffix(')') {
+                Some(scope) => (kind, Some(scope)),
+                None => return plain,
+            },
+            None => (head, None),
+        };
+        if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_alphabetic()) {
+            return plain;
+        }
+        Self {
+            kind: Some(kind),
+            scope,
+            description: description.trim(),
+        }
+    }
+}
+
+/// One commit line of the changelog.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub section: Section,
+    pub scope: Option<String>,
+    pub description: String,
+    /// Abbreviated commit hash.
+    pub hash: String,
+}
+
+impl Entry {
+    pub fn new(message: &str, hash: &str, rules: &[BumpRule]) -> Self {
+        let subject = Subject::parse(message);
+        Self {
+            section: Section::of(message, rules),
+            scope: subject.scope.map(str::to_string),
+            description: subject.description.to_string(),
+            hash: hash.to_string(),
+        }
+    }
+}
+
+/// The commits of one version, or of the unreleased changes when `date` is `None`.
+#[derive(Debug, Clone)]
+pub struct Release {
+    pub title: String,
+    pub date: Option<String>,
+    pub entries: Vec<Entry>,
+}
+
+/// Renders `releases` (newest first) as Markdown, one `##` heading per release and one
+/// `###` heading per non-empty section.
+pub fn render(releases: &[Release]) -> String {
+    let mut out = String::new();
+    for release in releases {
+        match &release.date {
+            Some(date) => out.push_str(&format!("## {} ({})\n\n", release.title, date)),
+            None => out.push_str(&format!("## {}\n\n", release.title)),
+        }
+        if release.entries.is_empty() {
+            out.push_str("No changes.\n\n");
+            continue;
+        }
+        let mut entries: Vec<&Entry> = release.entries.iter().collect();
+        // Stable sort: commits keep their history order within a section.
+        entries.sort_by_key(|entry| entry.section);
+        let mut current = None;
+        for entry in entries {
+            if current != Some(entry.section) {
+                if current.is_some() {
+                    out.push('\n');
+                }
+                out.push_str(&format!("### {}\n\n", entry.section.title()));
+                current = Some(entry.section);
+            }
+            match &entry.scope {
+                Some(scope) => out.push_str(&format!(
+                    "- **{}:** {} ({})\n",
+                    scope, entry.description, entry.hash
+                )),
+                None => out.push_str(&format!("- {} ({})\n", entry.description, entry.hash)),
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Title of the release holding the changes made since the last version.
+pub const UNRELEASED: &str = "Unreleased";
+
+/// Inserts the `releases` missing from `existing` above its releases, keeping a leading
+/// `# Title` line (and anything before the first `##` heading) at the top of the file.
+/// Releases whose heading is already in the file are skipped and an existing Unreleased
+/// section is replaced, so regenerating a changelog leaves it unchanged.
+pub fn prepend(existing: &str, releases: &[Release]) -> String {
+    let existing = without_unreleased(existing);
+    let titles: HashSet<&str> = existing.lines().filter_map(heading_title).collect();
+    let missing: Vec<Release> = releases
+        .iter()
+        .filter(|release| !titles.contains(release.title.as_str()))
+        .cloned()
+        .collect();
+
+    let split = if existing.starts_with("# ") {
+        existing
+            .match_indices("\n## ")
+            .next()
+            .map(|(i, _)| i + 1)
+            .unwrap_or(existing.len())
+    } else {
+        0
+    };
+    let (head, rest) = existing.split_at(split);
+    let mut out = head.to_string();
+    if !out.is_empty() && !out.ends_with("\n\n") {
+        out.push_str(if out.ends_with('\n') { "\n" } else { "\n\n" });
+    }
+    out.push_str(&render(&missing));
+    out.push_str(rest);
+    out
+}
+
+/// The release title of a `## <title>` or `## <title> (<date>)` heading line.
+fn heading_title(line: &str) -> Option<&str> {
+    let heading = line.trim_end().strip_prefix("## ")?;
+    Some(heading.split_once(" (").map_or(heading, |(title, _)| title))
+}
+
+fn without_unreleased(existing: &str) -> String {
+    let mut out = String::new();
+    let mut skipping = false;
+    for line in existing.split_inclusive('\n') {
+        if let Some(title) = heading_title(line) {
+            skipping = title == UNRELEASED;
+        }
+        if !skipping {
+            out.push_str(line);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules() -> Vec<BumpRule> {
+        versioning::conventional_bump_rules()
+    }
+
+    #[test]
+    fn test_sections_follow_bump_rules_then_type() {
+        let rules = rules();
+        assert_eq!(Section::of("feat!: drop v1", &rules), Section::Breaking);
+        assert_eq!(
+            Section::of("fix: typo\n\nBREAKING CHANGE: renamed", &rules),
+            Section::Breaking
+        );
+        assert_eq!(Section::of("feat(api): add", &rules), Section::Features);
+        assert_eq!(Section::of("fix(api): crash", &rules), Section::Fixes);
+        assert_eq!(Section::of("perf: faster", &rules), Section::Performance);
+        assert_eq!(Section::of("chore: bump deps", &rules), Section::Other);
+        assert_eq!(Section::of("Update README", &rules), Section::Other);
+    }
+
+    #[test]
+    fn test_custom_rules_drive_sections() {
+        let rules = vec![
+            BumpRule::new(r"^MAJOR:", Increment::Major).unwrap(),
+            BumpRule::new(r"^MINOR:", Increment::Minor).unwrap(),
+        ];
+        assert_eq!(Section::of("MAJOR: new storage", &rules), Section::Breaking);
+        assert_eq!(Section::of("MINOR: add export", &rules), Section::Features);
+        assert_eq!(Section::of("feat: not a rule", &rules), Section::Other);
+    }
+
+    #[test]
+    fn test_entry_splits_scope_and_description() {
+        let entry = Entry::new("feat(cli)!: add --json\n\nbody", "abc1234", &rules());
+        assert_eq!(entry.scope.as_deref(), Some("cli"));
+        assert_eq!(entry.description, "add --json");
+
+        let entry = Entry::new("Merge branch 'main'", "abc1234", &rules());
+        assert_eq!(entry.scope, None);
+        assert_eq!(entry.description, "Merge branch 'main'");
+    }
+
+    #[test]
+    fn test_render_groups_entries_per_release() {
+        let rules = rules();
+        let releases = vec![
+            Release {
+                title: "Unreleased".to_string(),
+                date: None,
+                entries: vec![],
+            },
+            Release {
+                title: "v1.1.0".to_string(),
+                date: Some("2024-05-01".to_string()),
+                entries: vec![
+                    Entry::new("fix: crash", "2222222", &rules),
+                    Entry::new("feat(api): export", "1111111", &rules),
+                ],
+            },
+        ];
+        assert_eq!(
+            render(&releases),
+            "## Unreleased\n\nNo changes.\n\n\
+             ## v1.1.0 (2024-05-01)\n\n\
+             ### Features\n\n- **api:** export (1111111)\n\n\
+             ### Bug Fixes\n\n- crash (2222222)\n\n"
+        );
+    }
+
+    #[test]
+    fn test_prepend_keeps_title_on_top() {
+        let existing = "# Changelog\n\nAll notable changes.\n\n## v1.0.0\n\n- first\n";
+        assert_eq!(
+            prepend(existing, &[release("v1.1.0", Some("2024-02-01"))]),
+            "# Changelog\n\nAll notable changes.\n\n## v1.1.0 (2024-02-01)\n\nNo changes.\n\n\
+             ## v1.0.0\n\n- first\n"
+        );
+        assert_eq!(
+            prepend("## v1.0.0\n", &[release("v1.1.0", None)]),
+            "## v1.1.0\n\nNo changes.\n\n## v1.0.0\n"
+        );
+    }
+
+    #[test]
+    fn test_prepend_skips_known_releases_and_replaces_unreleased() {
+        let existing =
+            "# Changelog\n\n## Unreleased\n\n- old\n\n## v1.0.0 (2024-01-01)\n\n- first\n";
+        let releases = [
+            release(UNRELEASED, None),
+            release("v1.1.0", Some("2024-02-01")),
+            release("v1.0.0", Some("2024-01-01")),
+        ];
+        assert_eq!(
+            prepend(existing, &releases),
+            "# Changelog\n\n## Unreleased\n\nNo changes.\n\n## v1.1.0 (2024-02-01)\n\n\
+             No changes.\n\n## v1.0.0 (2024-01-01)\n\n- first\n"
+        );
+    }
+
+    fn release(title: &str, date: Option<&str>) -> Release {
+        Release {
+            title: title.to_string(),
+            date: date.map(str::to_string),
+            entries: vec![],
+        }
+    }
+}
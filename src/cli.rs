@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use clap::{Args, Parser, Subcommand, ValueEnum};
 
 use crate::versioning::Increment;
@@ -17,10 +19,79 @@ pub struct Cli {
     )]
     pub verbose: bool,
 
+    #[clap(flatten)]
+    pub global: GlobalArgs,
+
     #[clap(subcommand)]
     pub command: Option<Commands>,
 }
 
+#[derive(Args, Debug, Clone)]
+pub struct GlobalArgs {
+    #[clap(
+        help = "Name of the remote to fetch from and push to",
+        long,
+        global = true,
+        default_value = "origin"
+    )]
+    pub remote: String,
+    #[clap(
+        help = "Skip fetching from the remote and resolve versions from local refs only",
+        long,
+        action,
+        global = true
+    )]
+    pub no_fetch: bool,
+    #[clap(
+        help = "Alias of --no-fetch for machines without network; --push is refused",
+        long,
+        action,
+        global = true
+    )]
+    pub offline: bool,
+    #[clap(
+        help = "Fetch the whole history of a shallow clone at once instead of deepening it step by step",
+        long,
+        action,
+        global = true
+    )]
+    pub unshallow: bool,
+    #[clap(
+        help = "Use the pattern, source and rules of a release stream declared in flopha.toml",
+        long,
+        global = true
+    )]
+    pub stream: Option<String>,
+    #[clap(
+        help = "Output format: human-readable text or one JSON document per command",
+        long,
+        global = true,
+        value_enum,
+        default_value_t
+    )]
+    pub format: OutputFormat,
+}
+
+impl Default for GlobalArgs {
+    fn default() -> Self {
+        Self {
+            remote: "origin".to_string(),
+            no_fetch: false,
+            offline: false,
+            unshallow: false,
+            stream: None,
+            format: OutputFormat::Text,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
 #[derive(Subcommand)]
 pub enum Commands {
     #[clap(
@@ -33,23 +104,43 @@ pub enum Commands {
         alias = "lv"
     )]
     LastVersion(LastVersionArgs),
+    #[clap(
+        about = "Describes HEAD as the release tagged on it or a development version such as v1.4.1-dev.7+g3f2a9c1. (alias: cv)",
+        alias = "cv"
+    )]
+    CurrentVersion(CurrentVersionArgs),
     #[clap(
         about = "Shows a timeline of all version tags matching a pattern. (alias: lg)",
         alias = "lg"
     )]
     Log(LogArgs),
+    #[clap(
+        about = "Writes the next version into project files, commits them and tags the release commit."
+    )]
+    Release(ReleaseArgs),
+    #[clap(
+        about = "Generates a Markdown changelog from the conventional commits of each release."
+    )]
+    Changelog(ChangelogArgs),
+    #[clap(
+        about = "Tags the latest pre-release's commit with its stable version, e.g. v2.4.0-rc.3 -> v2.4.0."
+    )]
+    Promote(PromoteArgs),
+    #[clap(about = "Lists the release streams declared in flopha.toml with their last version.")]
+    Streams,
 }
 
-#[derive(Args, Debug)]
-pub struct NextVersionArgs {
+/// How the next version is found: the bump, the pattern and where versions are read
+/// from. Shared by `next-version` and `release`.
+#[derive(Args, Debug, Clone, Default)]
+pub struct VersionArgs {
     #[clap(
-        help = "Specify the version part to increment: major, minor, or patch",
+        help = "Specify the version part to increment: major, minor, patch, or another \
+                numeric placeholder of the pattern such as build [default: patch]",
         long,
-        value_enum,
-        default_value = "patch",
         short = 'i'
     )]
-    pub increment: Increment,
+    pub increment: Option<Increment>,
     #[clap(
         help = "Auto-detect bump level from conventional commit messages since last tag. \
                 feat→minor, feat!/BREAKING CHANGE→major, else patch. \
@@ -66,41 +157,277 @@ pub struct NextVersionArgs {
                 Levels: major | minor | patch. \
                 Example: --rule 'major:BREAKING CHANGE' --rule 'minor:^feat'",
         long,
-        value_name = "LEVEL:PATTERN",
-        requires = "auto"
+        value_name = "LEVEL:PATTERN"
     )]
     pub rule: Vec<String>,
+    #[clap(
+        help = "With --auto, print each commit since the last version with the rule that \
+                matched it and the resulting bump, on stderr or as \"explain\" in JSON",
+        long,
+        action
+    )]
+    pub explain: bool,
+    #[clap(
+        help = "Only let commits touching these paths drive --auto (git pathspecs such as 'api/' or '*.rs'). \
+                Repeatable.",
+        long,
+        value_name = "GLOB"
+    )]
+    pub path: Vec<String>,
     #[clap(
         help = "Create a pre-release version on the given channel (e.g. alpha, beta, rc). \
                 Example: --pre alpha produces v1.2.3-alpha.1",
         long
     )]
     pub pre: Option<String>,
+    #[clap(
+        help = "Order of pre-release channels from least to most mature. --pre keeps the \
+                version of a pre-release in flight and refuses to move to an earlier channel. \
+                Default: alpha,beta,rc",
+        long,
+        value_delimiter = ',',
+        value_name = "CHANNELS"
+    )]
+    pub pre_channel_order: Vec<String>,
+    #[clap(
+        help = "Version to bump from when no version matches the pattern, e.g. 0.0.0. \
+                --auto then reads the whole history.",
+        long,
+        value_name = "VERSION"
+    )]
+    pub initial: Option<String>,
+    #[clap(
+        help = "Only consider tags on HEAD or its ancestors, e.g. to continue the 1.x line \
+                from a maintenance branch. --auto reads the commits since that version.",
+        long,
+        action,
+        overrides_with = "no_reachable"
+    )]
+    pub reachable: bool,
+    #[clap(
+        help = "Consider every version even if flopha.toml sets reachable = true",
+        long,
+        action,
+        overrides_with = "reachable"
+    )]
+    pub no_reachable: bool,
     #[clap(
         help = "Specify a custom pattern for version matching and generation. \
-                Use {major}, {minor}, and {patch} as placeholders. \
+                Use {major}, {minor}, and {patch} as placeholders, plus the optional \
+                {pre} and {build} SemVer suffixes. \
                 Example: 'v{major}.{minor}.{patch}' or 'release-{major}.{minor}.{patch}'",
         long,
         short = 'p'
     )]
     pub pattern: Option<String>,
+    #[clap(
+        help = "Specify the source for versioning: tag (default), branch or remote",
+        long,
+        short = 's',
+        value_enum
+    )]
+    pub source: Option<VersionSourceName>,
+}
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct NextVersionArgs {
+    #[command(flatten)]
+    pub version: VersionArgs,
+    #[clap(
+        help = "Compute the version at this commit instead of HEAD (e.g. a SHA or a branch): \
+                --auto reads the commits up to it and --create tags it without checking it out",
+        long,
+        value_name = "REVSPEC"
+    )]
+    pub rev: Option<String>,
+    #[clap(
+        help = "Write the next version, without the pattern's prefix, into the files given \
+                with --file. Fails without changing anything if a file or its version is missing.",
+        long,
+        action,
+        overrides_with = "no_write_files"
+    )]
+    pub write_files: bool,
+    #[clap(
+        help = "Do not write the version into files even if flopha.toml sets write-files = true",
+        long,
+        action,
+        overrides_with = "write_files"
+    )]
+    pub no_write_files: bool,
+    #[clap(
+        help = "File to write the version into with --write-files. Cargo.toml, package.json \
+                and pyproject.toml are recognised by name, other files hold just the version, \
+                and '<path>:<regex>' replaces the regex's (?P<version>...) group. Repeatable.",
+        long,
+        value_name = "FILE[:REGEX]"
+    )]
+    pub file: Vec<String>,
     #[clap(
         help = "Create a new tag or branch with the next version",
         long,
-        action
+        action,
+        overrides_with = "no_create"
     )]
     pub create: bool,
     #[clap(
-        help = "Specify the source for versioning: tag (default) or branch",
+        help = "Do not create a tag or branch even if flopha.toml sets create = true",
         long,
-        short = 's',
-        value_enum,
-        default_value = "tag"
+        action,
+        overrides_with = "create"
+    )]
+    pub no_create: bool,
+    #[clap(
+        help = "Push the created tag or branch to --remote. Requires --create.",
+        long,
+        action,
+        overrides_with = "no_push",
+        requires = "create"
+    )]
+    pub push: bool,
+    #[clap(
+        help = "Do not push even if flopha.toml sets push = true",
+        long,
+        action,
+        overrides_with = "push"
+    )]
+    pub no_push: bool,
+    #[clap(
+        help = "Create an annotated tag with a message and tagger instead of a lightweight tag. \
+                Requires --create.",
+        long,
+        action,
+        overrides_with = "no_annotate",
+        requires = "create"
+    )]
+    pub annotate: bool,
+    #[clap(
+        help = "Create a lightweight tag even if flopha.toml sets annotate = true",
+        long,
+        action,
+        overrides_with = "annotate"
+    )]
+    pub no_annotate: bool,
+    #[clap(
+        help = "Message of the annotated tag; {version} is replaced with the new version. \
+                Implies --annotate. Default: the version itself",
+        long,
+        short = 'm',
+        value_name = "TEXT",
+        requires = "create"
+    )]
+    pub message: Option<String>,
+    #[clap(
+        help = "Sign the tag using git's gpg.format and user.signingkey settings. \
+                Implies --annotate.",
+        long,
+        action,
+        overrides_with = "no_sign",
+        requires = "create"
+    )]
+    pub sign: bool,
+    #[clap(
+        help = "Do not sign the tag even if flopha.toml sets sign = true",
+        long,
+        action,
+        overrides_with = "sign"
+    )]
+    pub no_sign: bool,
+}
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct ReleaseArgs {
+    #[command(flatten)]
+    pub version: VersionArgs,
+    #[clap(
+        help = "File to write the version into and commit, as for next-version --file. Repeatable.",
+        long,
+        value_name = "FILE[:REGEX]"
+    )]
+    pub file: Vec<String>,
+    #[clap(
+        help = "Message of the release commit; {version} is replaced with the new version. \
+                Default: 'chore(release): {version}'",
+        long,
+        value_name = "TEXT"
+    )]
+    pub commit_message: Option<String>,
+    #[clap(
+        help = "Create an annotated tag instead of a lightweight tag",
+        long,
+        action,
+        overrides_with = "no_annotate"
+    )]
+    pub annotate: bool,
+    #[clap(
+        help = "Create a lightweight tag even if flopha.toml sets annotate = true",
+        long,
+        action,
+        overrides_with = "annotate"
+    )]
+    pub no_annotate: bool,
+    #[clap(
+        help = "Message of the annotated tag; {version} is replaced with the new version. \
+                Implies --annotate.",
+        long,
+        short = 'm',
+        value_name = "TEXT"
+    )]
+    pub message: Option<String>,
+    #[clap(
+        help = "Sign the tag using git's gpg.format and user.signingkey settings. \
+                Implies --annotate.",
+        long,
+        action,
+        overrides_with = "no_sign"
+    )]
+    pub sign: bool,
+    #[clap(
+        help = "Do not sign the tag even if flopha.toml sets sign = true",
+        long,
+        action,
+        overrides_with = "sign"
+    )]
+    pub no_sign: bool,
+    #[clap(
+        help = "Push the current branch and the new tag or branch to --remote",
+        long,
+        action,
+        overrides_with = "no_push"
+    )]
+    pub push: bool,
+    #[clap(
+        help = "Do not push even if flopha.toml sets push = true",
+        long,
+        action,
+        overrides_with = "push"
     )]
-    pub source: VersionSourceName,
+    pub no_push: bool,
+}
+
+impl ReleaseArgs {
+    /// The `next-version --write-files --create` equivalent of this release.
+    pub fn next_version_args(&self) -> NextVersionArgs {
+        NextVersionArgs {
+            version: self.version.clone(),
+            rev: None,
+            write_files: true,
+            no_write_files: false,
+            file: self.file.clone(),
+            create: true,
+            no_create: false,
+            push: self.push,
+            no_push: self.no_push,
+            annotate: self.annotate,
+            no_annotate: self.no_annotate,
+            message: self.message.clone(),
+            sign: self.sign,
+            no_sign: self.no_sign,
+        }
+    }
 }
 
-#[derive(Args, Debug)]
+#[derive(Args, Debug, Clone, Default)]
 pub struct LastVersionArgs {
     #[clap(
         help = "Get last version based on a given pattern (e.g., 'v{major}.{minor}.{patch}')",
@@ -109,18 +436,75 @@ pub struct LastVersionArgs {
     )]
     pub pattern: Option<String>,
     #[clap(
-        help = "Specify the source for versioning: tag (default) or branch",
+        help = "Specify the source for versioning: tag (default), branch or remote",
         long,
         value_enum,
-        default_value = "tag",
         short = 's'
     )]
-    pub source: VersionSourceName,
-    #[clap(help = "Checkout the last version", long, action)]
+    pub source: Option<VersionSourceName>,
+    #[clap(
+        help = "Checkout the last version",
+        long,
+        action,
+        overrides_with = "no_checkout"
+    )]
     pub checkout: bool,
+    #[clap(
+        help = "Do not checkout the last version even if flopha.toml sets checkout = true",
+        long,
+        action,
+        overrides_with = "checkout"
+    )]
+    pub no_checkout: bool,
+    #[clap(
+        help = "Only consider tags on HEAD or its ancestors instead of the whole repository",
+        long,
+        action,
+        overrides_with = "no_reachable"
+    )]
+    pub reachable: bool,
+    #[clap(
+        help = "Consider every version even if flopha.toml sets reachable = true",
+        long,
+        action,
+        overrides_with = "reachable"
+    )]
+    pub no_reachable: bool,
+    #[clap(
+        help = "Print the last version reachable from this commit instead of the latest \
+                one in the repository. Implies --reachable.",
+        long,
+        value_name = "REVSPEC"
+    )]
+    pub rev: Option<String>,
+}
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct CurrentVersionArgs {
+    #[clap(
+        help = "Pattern of the release versions (e.g., 'v{major}.{minor}.{patch}')",
+        long,
+        short = 'p'
+    )]
+    pub pattern: Option<String>,
+    #[clap(
+        help = "Specify the source for versioning: tag (default), branch or remote",
+        long,
+        value_enum,
+        short = 's'
+    )]
+    pub source: Option<VersionSourceName>,
+    #[clap(
+        help = "Template of development versions. Tokens: {version} (last release), {next} \
+                (its next patch version), {distance}, {sha}, {dirty} and {branch} \
+                [default: {next}-dev.{distance}+g{sha}{dirty}]",
+        long,
+        short = 't'
+    )]
+    pub template: Option<String>,
 }
 
-#[derive(Args, Debug)]
+#[derive(Args, Debug, Clone, Default)]
 pub struct LogArgs {
     #[clap(
         help = "Pattern for version matching (e.g., 'v{major}.{minor}.{patch}')",
@@ -129,23 +513,125 @@ pub struct LogArgs {
     )]
     pub pattern: Option<String>,
     #[clap(
-        help = "Specify the source for versioning: tag (default) or branch",
+        help = "Specify the source for versioning: tag (default), branch or remote",
         long,
         short = 's',
-        value_enum,
-        default_value = "tag"
+        value_enum
     )]
-    pub source: VersionSourceName,
+    pub source: Option<VersionSourceName>,
     #[clap(
         help = "Maximum number of versions to show (default: all)",
         long,
         short = 'n'
     )]
     pub limit: Option<usize>,
+    #[clap(
+        help = "Only count commits touching these paths (git pathspecs such as 'api/' or '*.rs'). \
+                Repeatable.",
+        long,
+        value_name = "GLOB"
+    )]
+    pub path: Vec<String>,
+}
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct ChangelogArgs {
+    #[clap(
+        help = "Pattern for version matching (e.g., 'v{major}.{minor}.{patch}')",
+        long,
+        short = 'p'
+    )]
+    pub pattern: Option<String>,
+    #[clap(
+        help = "Custom rule as '<level>:<regex>'; commits matching major rules are listed as \
+                breaking changes and minor rules as features. Repeatable; replaces the \
+                built-in conventional-commit defaults.",
+        long,
+        value_name = "LEVEL:PATTERN"
+    )]
+    pub rule: Vec<String>,
+    #[clap(
+        help = "Only include commits touching these paths (git pathspecs such as 'api/' or '*.rs'). \
+                Repeatable.",
+        long,
+        value_name = "GLOB"
+    )]
+    pub path: Vec<String>,
+    #[clap(
+        help = "Only include releases newer than this version",
+        long,
+        value_name = "VERSION",
+        conflicts_with = "unreleased"
+    )]
+    pub since: Option<String>,
+    #[clap(
+        help = "Only include the commits made since the last version",
+        long,
+        action
+    )]
+    pub unreleased: bool,
+    #[clap(
+        help = "Prepend the changelog to this file instead of printing it (e.g. CHANGELOG.md). \
+                Releases already in the file are skipped and its Unreleased section is replaced.",
+        long,
+        short = 'o',
+        value_name = "FILE"
+    )]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct PromoteArgs {
+    #[clap(
+        help = "Only promote pre-releases on this channel (e.g. rc). Default: any channel",
+        long
+    )]
+    pub pre: Option<String>,
+    #[clap(
+        help = "Pattern of the stable version (e.g., 'v{major}.{minor}.{patch}')",
+        long,
+        short = 'p'
+    )]
+    pub pattern: Option<String>,
+    #[clap(help = "Push the created tag to --remote", long, action)]
+    pub push: bool,
+    #[clap(
+        help = "Create an annotated tag with a message and tagger instead of a lightweight tag",
+        long,
+        action
+    )]
+    pub annotate: bool,
+    #[clap(
+        help = "Message of the annotated tag; {version} is replaced with the stable version. \
+                Implies --annotate. Default: the version itself",
+        long,
+        short = 'm',
+        value_name = "TEXT"
+    )]
+    pub message: Option<String>,
+    #[clap(
+        help = "Sign the tag using git's gpg.format and user.signingkey settings. \
+                Implies --annotate.",
+        long,
+        action
+    )]
+    pub sign: bool,
 }
 
-#[derive(Debug, Clone, ValueEnum)]
+#[derive(Debug, Clone, Default, ValueEnum)]
 pub enum VersionSourceName {
+    #[default]
     Tag,
     Branch,
+    Remote,
+}
+
+impl VersionSourceName {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VersionSourceName::Tag => "tag",
+            VersionSourceName::Branch => "branch",
+            VersionSourceName::Remote => "remote",
+        }
+    }
 }
@@ -0,0 +1,802 @@
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+
+use crate::cli::{
+    ChangelogArgs, CurrentVersionArgs, LastVersionArgs, LogArgs, NextVersionArgs, PromoteArgs,
+    VersionArgs, VersionSourceName,
+};
+use crate::error::FlophaError;
+use crate::versioning::{self, Increment};
+
+pub const CONFIG_FILE: &str = "flopha.toml";
+const PYPROJECT_FILE: &str = "pyproject.toml";
+
+/// Project defaults read from `flopha.toml` (or `[tool.flopha]` in `pyproject.toml`).
+///
+/// Top-level `pattern`, `source` and `rules` apply to every command; the
+/// `[next-version]`, `[last-version]`, `[current-version]`, `[log]` and `[changelog]`
+/// tables override them per command.
+/// A stream selected with `--stream` overrides both, and flags given on the command
+/// line always win over the file.
+#[derive(Debug, Default, Clone)]
+pub struct Config {
+    pub pattern: Option<String>,
+    pub source: Option<VersionSourceName>,
+    pub rules: Vec<String>,
+    pub next_version: NextVersionConfig,
+    pub last_version: LastVersionConfig,
+    pub current_version: CurrentVersionConfig,
+    pub log: LogConfig,
+    pub changelog: ChangelogConfig,
+    pub release: ReleaseConfig,
+    pub streams: Vec<(String, StreamConfig)>,
+    /// The stream picked with `--stream`, if any.
+    pub stream: Option<StreamConfig>,
+}
+
+/// A named release line declared as `[streams.<name>]`, e.g. one package of a monorepo.
+#[derive(Debug, Default, Clone)]
+pub struct StreamConfig {
+    pub pattern: String,
+    pub source: Option<VersionSourceName>,
+    pub rules: Vec<String>,
+    pub paths: Vec<String>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct NextVersionConfig {
+    pub pattern: Option<String>,
+    pub source: Option<VersionSourceName>,
+    pub increment: Option<Increment>,
+    pub auto: Option<bool>,
+    pub rules: Vec<String>,
+    pub paths: Vec<String>,
+    pub pre: Option<String>,
+    pub pre_channel_order: Vec<String>,
+    pub initial: Option<String>,
+    pub reachable: Option<bool>,
+    pub write_files: Option<bool>,
+    pub files: Vec<String>,
+    pub create: Option<bool>,
+    pub push: Option<bool>,
+    pub annotate: Option<bool>,
+    pub message: Option<String>,
+    pub sign: Option<bool>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct LastVersionConfig {
+    pub pattern: Option<String>,
+    pub source: Option<VersionSourceName>,
+    pub checkout: Option<bool>,
+    pub reachable: Option<bool>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct CurrentVersionConfig {
+    pub pattern: Option<String>,
+    pub source: Option<VersionSourceName>,
+    pub template: Option<String>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct LogConfig {
+    pub pattern: Option<String>,
+    pub source: Option<VersionSourceName>,
+    pub limit: Option<usize>,
+    pub paths: Vec<String>,
+}
+
+/// `[release]` only holds what is specific to the release commit; the version, files and
+/// tag options of `release` come from `[next-version]`.
+#[derive(Debug, Default, Clone)]
+pub struct ReleaseConfig {
+    pub commit_message: Option<String>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ChangelogConfig {
+    pub pattern: Option<String>,
+    pub rules: Vec<String>,
+    pub paths: Vec<String>,
+    pub output: Option<PathBuf>,
+}
+
+impl Config {
+    /// Loads the configuration from `dir`, returning an empty config when no file exists.
+    pub fn load(dir: &Path) -> Result<Self, FlophaError> {
+        let path = dir.join(CONFIG_FILE);
+        if path.is_file() {
+            let file = path.display().to_string();
+            let table = read_table(&path)?;
+            return Self::from_section(&Section::root(&file, &table));
+        }
+
+        let path = dir.join(PYPROJECT_FILE);
+        if path.is_file() {
+            let file = path.display().to_string();
+            let table = read_table(&path)?;
+            let tool = Section::root(&file, &table);
+            if let Some(tool) = tool.optional_table("tool")? {
+                if let Some(flopha) = tool.optional_table("flopha")? {
+                    return Self::from_section(&flopha);
+                }
+            }
+        }
+
+        Ok(Self::default())
+    }
+
+    fn from_section(root: &Section) -> Result<Self, FlophaError> {
+        root.check_keys(&[
+            "pattern",
+            "source",
+            "rules",
+            "next-version",
+            "last-version",
+            "current-version",
+            "log",
+            "changelog",
+            "release",
+            "streams",
+        ])?;
+        let mut config = Self {
+            pattern: root.string("pattern")?,
+            source: root.value_enum("source")?,
+            rules: root.rules("rules")?,
+            ..Self::default()
+        };
+
+        if let Some(section) = root.optional_table("next-version")? {
+            section.check_keys(&[
+                "pattern",
+                "source",
+                "increment",
+                "auto",
+                "rules",
+                "paths",
+                "pre",
+                "pre-channel-order",
+                "initial",
+                "reachable",
+                "write-files",
+                "files",
+                "create",
+                "push",
+                "annotate",
+                "message",
+                "sign",
+            ])?;
+            config.next_version = NextVersionConfig {
+                pattern: section.string("pattern")?,
+                source: section.value_enum("source")?,
+                increment: section.parsed("increment")?,
+                auto: section.bool("auto")?,
+                rules: section.rules("rules")?,
+                paths: section.strings("paths")?,
+                pre: section.string("pre")?,
+                pre_channel_order: section.strings("pre-channel-order")?,
+                initial: section.string("initial")?,
+                reachable: section.bool("reachable")?,
+                write_files: section.bool("write-files")?,
+                files: section.strings("files")?,
+                create: section.bool("create")?,
+                push: section.bool("push")?,
+                annotate: section.bool("annotate")?,
+                message: section.string("message")?,
+                sign: section.bool("sign")?,
+            };
+        }
+
+        if let Some(section) = root.optional_table("last-version")? {
+            section.check_keys(&["pattern", "source", "checkout", "reachable"])?;
+            config.last_version = LastVersionConfig {
+                pattern: section.string("pattern")?,
+                source: section.value_enum("source")?,
+                checkout: section.bool("checkout")?,
+                reachable: section.bool("reachable")?,
+            };
+        }
+
+        if let Some(section) = root.optional_table("current-version")? {
+            section.check_keys(&["pattern", "source", "template"])?;
+            config.current_version = CurrentVersionConfig {
+                pattern: section.string("pattern")?,
+                source: section.value_enum("source")?,
+                template: section.string("template")?,
+            };
+        }
+
+        if let Some(section) = root.optional_table("log")? {
+            section.check_keys(&["pattern", "source", "limit", "paths"])?;
+            config.log = LogConfig {
+                pattern: section.string("pattern")?,
+                source: section.value_enum("source")?,
+                limit: section.usize("limit")?,
+                paths: section.strings("paths")?,
+            };
+        }
+
+        if let Some(section) = root.optional_table("changelog")? {
+            section.check_keys(&["pattern", "rules", "paths", "output"])?;
+            config.changelog = ChangelogConfig {
+                pattern: section.string("pattern")?,
+                rules: section.rules("rules")?,
+                paths: section.strings("paths")?,
+                output: section.string("output")?.map(PathBuf::from),
+            };
+        }
+
+        if let Some(section) = root.optional_table("release")? {
+            section.check_keys(&["commit-message"])?;
+            config.release = ReleaseConfig {
+                commit_message: section.string("commit-message")?,
+            };
+        }
+
+        if let Some(streams) = root.optional_table("streams")? {
+            for name in streams.table.keys() {
+                let section = streams
+                    .optional_table(name)?
+                    .ok_or_else(|| streams.invalid(name, "expected a table"))?;
+                section.check_keys(&["pattern", "source", "rules", "paths"])?;
+                let stream = StreamConfig {
+                    pattern: section
+                        .string("pattern")?
+                        .ok_or_else(|| section.invalid("pattern", "missing required key"))?,
+                    source: section.value_enum("source")?,
+                    rules: section.rules("rules")?,
+                    paths: section.strings("paths")?,
+                };
+                config.streams.push((name.clone(), stream));
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Makes the stream called `name` override the file's top-level and per-command defaults.
+    pub fn select_stream(&mut self, name: &str) -> Result<(), FlophaError> {
+        let stream = self
+            .streams
+            .iter()
+            .find(|(stream_name, _)| stream_name == name)
+            .map(|(_, stream)| stream.clone())
+            .ok_or_else(|| FlophaError::StreamNotFound(name.to_string()))?;
+        self.stream = Some(stream);
+        Ok(())
+    }
+
+    /// Fills every option not given on the command line from the config file.
+    pub fn next_version_args(&self, args: &NextVersionArgs) -> NextVersionArgs {
+        let section = &self.next_version;
+        let version = &args.version;
+        // An explicit --increment on the command line overrides `auto = true` in the file.
+        let auto =
+            version.auto || (version.increment.is_none() && section.auto.unwrap_or_default());
+        NextVersionArgs {
+            version: VersionArgs {
+                increment: version.increment.clone().or(section.increment.clone()),
+                auto,
+                rule: self.rules(&version.rule, &section.rules),
+                explain: version.explain,
+                path: self.paths(&version.path, &section.paths),
+                pre: version.pre.clone().or(section.pre.clone()),
+                pre_channel_order: if version.pre_channel_order.is_empty() {
+                    section.pre_channel_order.clone()
+                } else {
+                    version.pre_channel_order.clone()
+                },
+                initial: version.initial.clone().or(section.initial.clone()),
+                reachable: flag(version.reachable, version.no_reachable, section.reachable),
+                no_reachable: false,
+                pattern: self.pattern(&version.pattern, &section.pattern),
+                source: self.source(&version.source, &section.source),
+            },
+            rev: args.rev.clone(),
+            write_files: flag(args.write_files, args.no_write_files, section.write_files),
+            no_write_files: false,
+            file: if args.file.is_empty() {
+                section.files.clone()
+            } else {
+                args.file.clone()
+            },
+            create: flag(args.create, args.no_create, section.create),
+            no_create: false,
+            push: flag(args.push, args.no_push, section.push),
+            no_push: false,
+            annotate: flag(args.annotate, args.no_annotate, section.annotate),
+            no_annotate: false,
+            message: args.message.clone().or(section.message.clone()),
+            sign: flag(args.sign, args.no_sign, section.sign),
+            no_sign: false,
+        }
+    }
+
+    pub fn last_version_args(&self, args: &LastVersionArgs) -> LastVersionArgs {
+        let section = &self.last_version;
+        LastVersionArgs {
+            pattern: self.pattern(&args.pattern, &section.pattern),
+            source: self.source(&args.source, &section.source),
+            checkout: flag(args.checkout, args.no_checkout, section.checkout),
+            no_checkout: false,
+            reachable: flag(args.reachable, args.no_reachable, section.reachable),
+            no_reachable: false,
+            rev: args.rev.clone(),
+        }
+    }
+
+    pub fn current_version_args(&self, args: &CurrentVersionArgs) -> CurrentVersionArgs {
+        let section = &self.current_version;
+        CurrentVersionArgs {
+            pattern: self.pattern(&args.pattern, &section.pattern),
+            source: self.source(&args.source, &section.source),
+            template: args.template.clone().or(section.template.clone()),
+        }
+    }
+
+    pub fn log_args(&self, args: &LogArgs) -> LogArgs {
+        let section = &self.log;
+        LogArgs {
+            pattern: self.pattern(&args.pattern, &section.pattern),
+            source: self.source(&args.source, &section.source),
+            limit: args.limit.or(section.limit),
+            path: self.paths(&args.path, &section.paths),
+        }
+    }
+
+    pub fn changelog_args(&self, args: &ChangelogArgs) -> ChangelogArgs {
+        let section = &self.changelog;
+        ChangelogArgs {
+            pattern: self.pattern(&args.pattern, &section.pattern),
+            rule: self.rules(&args.rule, &section.rules),
+            path: self.paths(&args.path, &section.paths),
+            since: args.since.clone(),
+            unreleased: args.unreleased,
+            output: args.output.clone().or(section.output.clone()),
+        }
+    }
+
+    pub fn promote_args(&self, args: &PromoteArgs) -> PromoteArgs {
+        PromoteArgs {
+            pre: args.pre.clone(),
+            pattern: self.pattern(&args.pattern, &None),
+            push: args.push,
+            annotate: args.annotate,
+            message: args.message.clone(),
+            sign: args.sign,
+        }
+    }
+
+    fn pattern(&self, cli: &Option<String>, section: &Option<String>) -> Option<String> {
+        cli.clone()
+            .or_else(|| self.stream.as_ref().map(|s| s.pattern.clone()))
+            .or_else(|| section.clone())
+            .or_else(|| self.pattern.clone())
+    }
+
+    fn rules(&self, cli: &[String], section: &[String]) -> Vec<String> {
+        let stream_rules = self.stream.as_ref().map(|s| s.rules.as_slice());
+        [
+            Some(cli),
+            stream_rules,
+            Some(section),
+            Some(self.rules.as_slice()),
+        ]
+        .into_iter()
+        .flatten()
+        .find(|rules| !rules.is_empty())
+        .unwrap_or_default()
+        .to_vec()
+    }
+
+    fn paths(&self, cli: &[String], section: &[String]) -> Vec<String> {
+        let stream_paths = self.stream.as_ref().map(|s| s.paths.as_slice());
+        [Some(cli), stream_paths, Some(section)]
+            .into_iter()
+            .flatten()
+            .find(|paths| !paths.is_empty())
+            .unwrap_or_default()
+            .to_vec()
+    }
+
+    fn source(
+        &self,
+        cli: &Option<VersionSourceName>,
+        section: &Option<VersionSourceName>,
+    ) -> Option<VersionSourceName> {
+        cli.clone()
+            .or_else(|| self.stream.as_ref().and_then(|s| s.source.clone()))
+            .or_else(|| section.clone())
+            .or_else(|| self.source.clone())
+    }
+}
+
+fn read_table(path: &Path) -> Result<toml::Table, FlophaError> {
+    let file = path.display().to_string();
+    let content = std::fs::read_to_string(path).map_err(|e| FlophaError::InvalidConfig {
+        path: file.clone(),
+        key: String::new(),
+        reason: e.to_string(),
+    })?;
+    content
+        .parse::<toml::Table>()
+        .map_err(|e| FlophaError::InvalidConfig {
+            path: file,
+            key: String::new(),
+            reason: e.message().to_string(),
+        })
+}
+
+/// A TOML table together with its file and dotted key prefix, so that every
+/// validation error can name the exact key that caused it.
+struct Section<'a> {
+    file: &'a str,
+    prefix: String,
+    table: &'a toml::Table,
+}
+
+impl<'a> Section<'a> {
+    fn root(file: &'a str, table: &'a toml::Table) -> Self {
+        Self {
+            file,
+            prefix: String::new(),
+            table,
+        }
+    }
+
+    fn key(&self, key: &str) -> String {
+        format!("{}{}", self.prefix, key)
+    }
+
+    fn invalid(&self, key: &str, reason: impl Into<String>) -> FlophaError {
+        FlophaError::InvalidConfig {
+            path: self.file.to_string(),
+            key: self.key(key),
+            reason: reason.into(),
+        }
+    }
+
+    fn check_keys(&self, allowed: &[&str]) -> Result<(), FlophaError> {
+        match self.table.keys().find(|k| !allowed.contains(&k.as_str())) {
+            Some(key) => Err(self.invalid(key, "unknown key")),
+            None => Ok(()),
+        }
+    }
+
+    fn optional_table(&self, key: &str) -> Result<Option<Section<'a>>, FlophaError> {
+        match self.table.get(key) {
+            None => Ok(None),
+            Some(toml::Value::Table(table)) => Ok(Some(Section {
+                file: self.file,
+                prefix: format!("{}.", self.key(key)),
+                table,
+            })),
+            Some(_) => Err(self.invalid(key, "expected a table")),
+        }
+    }
+
+    fn string(&self, key: &str) -> Result<Option<String>, FlophaError> {
+        match self.table.get(key) {
+            None => Ok(None),
+            Some(toml::Value::String(s)) => Ok(Some(s.clone())),
+            Some(_) => Err(self.invalid(key, "expected a string")),
+        }
+    }
+
+    fn bool(&self, key: &str) -> Result<Option<bool>, FlophaError> {
+        match self.table.get(key) {
+            None => Ok(None),
+            Some(toml::Value::Boolean(b)) => Ok(Some(*b)),
+            Some(_) => Err(self.invalid(key, "expected a boolean")),
+        }
+    }
+
+    fn usize(&self, key: &str) -> Result<Option<usize>, FlophaError> {
+        match self.table.get(key) {
+            None => Ok(None),
+            Some(toml::Value::Integer(n)) => usize::try_from(*n)
+                .map(Some)
+                .map_err(|_| self.invalid(key, "expected a non-negative integer")),
+            Some(_) => Err(self.invalid(key, "expected an integer")),
+        }
+    }
+
+    fn value_enum<T: ValueEnum>(&self, key: &str) -> Result<Option<T>, FlophaError> {
+        let Some(value) = self.string(key)? else {
+            return Ok(None);
+        };
+        T::from_str(&value, true).map(Some).map_err(|_| {
+            let expected: Vec<String> = T::value_variants()
+                .iter()
+                .filter_map(|v| v.to_possible_value())
+                .map(|v| v.get_name().to_string())
+                .collect();
+            self.invalid(
+                key,
+                format!(
+                    "unknown value '{}', expected {}",
+                    value,
+                    expected.join(", ")
+                ),
+            )
+        })
+    }
+
+    fn parsed<T: std::str::FromStr<Err = String>>(
+        &self,
+        key: &str,
+    ) -> Result<Option<T>, FlophaError> {
+        let Some(value) = self.string(key)? else {
+            return Ok(None);
+        };
+        value
+            .parse()
+            .map(Some)
+            .map_err(|reason| self.invalid(key, reason))
+    }
+
+    fn strings(&self, key: &str) -> Result<Vec<String>, FlophaError> {
+        match self.table.get(key) {
+            None => Ok(Vec::new()),
+            Some(toml::Value::Array(items)) => items
+                .iter()
+                .map(|item| match item {
+                    toml::Value::String(s) => Ok(s.clone()),
+                    _ => Err(self.invalid(key, "expected an array of strings")),
+                })
+                .collect(),
+            Some(_) => Err(self.invalid(key, "expected an array of strings")),
+        }
+    }
+
+    /// Reads `<level>:<regex>` rule strings, validating each one up front.
+    fn rules(&self, key: &str) -> Result<Vec<String>, FlophaError> {
+        let rules = self.strings(key)?;
+        for rule in &rules {
+            versioning::parse_bump_rule(rule).map_err(|e| self.invalid(key, e.to_string()))?;
+        }
+        Ok(rules)
+    }
+}
+
+/// Resolves a boolean option: `--<flag>` and `--no-<flag>` win over the file, which wins
+/// over the default of false.
+fn flag(on: bool, off: bool, file: Option<bool>) -> bool {
+    on || (!off && file.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_config(name: &str, content: &str) -> TempDir {
+        let td = TempDir::new().unwrap();
+        std::fs::write(td.path().join(name), content).unwrap();
+        td
+    }
+
+    #[test]
+    fn test_missing_file_is_empty_config() {
+        let td = TempDir::new().unwrap();
+        let config = Config::load(td.path()).unwrap();
+        assert!(config.pattern.is_none());
+        assert!(config.rules.is_empty());
+    }
+
+    #[test]
+    fn test_section_overrides_top_level_and_cli_overrides_section() {
+        let td = write_config(
+            CONFIG_FILE,
+            r#"
+pattern = "desktop@{major}.{minor}.{patch}"
+source = "branch"
+
+[next-version]
+pattern = "app@{major}.{minor}.{patch}"
+auto = true
+rules = ["major:BREAKING", "minor:^feat"]
+"#,
+        );
+        let config = Config::load(td.path()).unwrap();
+
+        let args = config.next_version_args(&NextVersionArgs::default());
+        assert_eq!(
+            args.version.pattern.as_deref(),
+            Some("app@{major}.{minor}.{patch}")
+        );
+        assert!(matches!(
+            args.version.source,
+            Some(VersionSourceName::Branch)
+        ));
+        assert!(args.version.auto);
+        assert_eq!(args.version.rule, vec!["major:BREAKING", "minor:^feat"]);
+
+        let cli = NextVersionArgs {
+            version: VersionArgs {
+                pattern: Some("v{major}.{minor}.{patch}".to_string()),
+                increment: Some(Increment::Major),
+                rule: vec!["major:^boom".to_string()],
+                ..VersionArgs::default()
+            },
+            ..NextVersionArgs::default()
+        };
+        let args = config.next_version_args(&cli);
+        assert_eq!(
+            args.version.pattern.as_deref(),
+            Some("v{major}.{minor}.{patch}")
+        );
+        assert!(matches!(args.version.increment, Some(Increment::Major)));
+        assert!(!args.version.auto);
+        assert_eq!(args.version.rule, vec!["major:^boom"]);
+
+        let last = config.last_version_args(&LastVersionArgs::default());
+        assert_eq!(
+            last.pattern.as_deref(),
+            Some("desktop@{major}.{minor}.{patch}")
+        );
+    }
+
+    #[test]
+    fn test_no_flags_override_file_true() {
+        let td = write_config(
+            CONFIG_FILE,
+            r#"
+[next-version]
+create = true
+push = true
+sign = true
+reachable = true
+
+[last-version]
+checkout = true
+"#,
+        );
+        let config = Config::load(td.path()).unwrap();
+
+        let args = config.next_version_args(&NextVersionArgs::default());
+        assert!(args.create && args.push && args.sign && args.version.reachable);
+
+        let cli = NextVersionArgs {
+            version: VersionArgs {
+                no_reachable: true,
+                ..VersionArgs::default()
+            },
+            no_push: true,
+            no_sign: true,
+            ..NextVersionArgs::default()
+        };
+        let args = config.next_version_args(&cli);
+        assert!(args.create);
+        assert!(!args.push && !args.sign && !args.version.reachable);
+
+        let cli = LastVersionArgs {
+            no_checkout: true,
+            ..LastVersionArgs::default()
+        };
+        assert!(!config.last_version_args(&cli).checkout);
+    }
+
+    #[test]
+    fn test_selected_stream_overrides_file_defaults() {
+        let td = write_config(
+            CONFIG_FILE,
+            r#"
+pattern = "v{major}.{minor}.{patch}"
+rules = ["minor:^feat"]
+
+[next-version]
+source = "tag"
+
+[streams.api]
+pattern = "api@{major}.{minor}.{patch}"
+source = "branch"
+rules = ["major:^api!"]
+paths = ["api/"]
+
+[streams.desktop]
+pattern = "desktop@{major}.{minor}.{patch}"
+"#,
+        );
+        let mut config = Config::load(td.path()).unwrap();
+        let names: Vec<&str> = config.streams.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, vec!["api", "desktop"]);
+
+        config.select_stream("api").unwrap();
+        let args = config.next_version_args(&NextVersionArgs::default());
+        assert_eq!(
+            args.version.pattern.as_deref(),
+            Some("api@{major}.{minor}.{patch}")
+        );
+        assert!(matches!(
+            args.version.source,
+            Some(VersionSourceName::Branch)
+        ));
+        assert_eq!(args.version.rule, vec!["major:^api!"]);
+        assert_eq!(args.version.path, vec!["api/"]);
+
+        config.select_stream("desktop").unwrap();
+        let args = config.next_version_args(&NextVersionArgs::default());
+        assert_eq!(
+            args.version.pattern.as_deref(),
+            Some("desktop@{major}.{minor}.{patch}")
+        );
+        assert!(matches!(args.version.source, Some(VersionSourceName::Tag)));
+        assert_eq!(args.version.rule, vec!["minor:^feat"]);
+
+        assert!(matches!(
+            config.select_stream("mobile"),
+            Err(FlophaError::StreamNotFound(ref name)) if name == "mobile"
+        ));
+    }
+
+    #[test]
+    fn test_stream_without_pattern_is_rejected() {
+        let td = write_config(CONFIG_FILE, "[streams.api]\nsource = \"tag\"\n");
+        let err = Config::load(td.path()).unwrap_err();
+        assert!(matches!(
+            err,
+            FlophaError::InvalidConfig { ref key, .. } if key == "streams.api.pattern"
+        ));
+    }
+
+    #[test]
+    fn test_reads_tool_flopha_from_pyproject() {
+        let td = write_config(
+            PYPROJECT_FILE,
+            r#"
+[project]
+name = "app"
+
+[tool.flopha.log]
+limit = 5
+"#,
+        );
+        let config = Config::load(td.path()).unwrap();
+        assert_eq!(config.log.limit, Some(5));
+    }
+
+    #[test]
+    fn test_invalid_value_names_file_and_key() {
+        let td = write_config(CONFIG_FILE, "[next-version]\nincrement = \"Huge\"\n");
+        let err = Config::load(td.path()).unwrap_err();
+        assert!(matches!(
+            err,
+            FlophaError::InvalidConfig { ref path, ref key, .. }
+                if path.ends_with(CONFIG_FILE) && key == "next-version.increment"
+        ));
+    }
+
+    #[test]
+    fn test_increment_may_name_a_placeholder_of_another_pattern() {
+        // The pattern may come from --pattern or a stream, so it is checked when bumping.
+        let td = write_config(CONFIG_FILE, "[next-version]\nincrement = \"build\"\n");
+        let config = Config::load(td.path()).unwrap();
+        assert_eq!(
+            config.next_version.increment,
+            Some(Increment::Component("build".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_invalid_rule_names_key() {
+        let td = write_config(CONFIG_FILE, "rules = [\"huge:^feat\"]\n");
+        let err = Config::load(td.path()).unwrap_err();
+        assert!(matches!(
+            err,
+            FlophaError::InvalidConfig { ref key, .. } if key == "rules"
+        ));
+    }
+
+    #[test]
+    fn test_unknown_key_is_rejected() {
+        let td = write_config(PYPROJECT_FILE, "[tool.flopha]\npaterns = \"v{major}\"\n");
+        let err = Config::load(td.path()).unwrap_err();
+        assert!(matches!(
+            err,
+            FlophaError::InvalidConfig { ref key, .. } if key == "tool.flopha.paterns"
+        ));
+    }
+}
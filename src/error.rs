@@ -4,20 +4,133 @@ use thiserror::Error;
 pub enum FlophaError {
     #[error("git error: {0}")]
     Git(#[from] git2::Error),
-    #[error("failed to open repository at '{path}'")]
+    #[error("failed to open repository at '{path}': {source}")]
     RepoNotFound {
         path: String,
         #[source]
         source: git2::Error,
     },
-    #[error("remote '{name}' not found")]
+    #[error("remote '{name}' not found: {source}")]
     RemoteNotFound {
         name: String,
         #[source]
         source: git2::Error,
     },
+    #[error("failed to push '{name}' to remote '{remote}': {source}")]
+    PushRejected {
+        name: String,
+        remote: String,
+        #[source]
+        source: git2::Error,
+    },
     #[error("version component '{{{0}}}' not present in pattern")]
     MissingVersionComponent(String),
+    #[error("the history{} is not in this shallow clone; fetch it with `git fetch --unshallow` or pass --unshallow", since_tag(.tag))]
+    ShallowHistory { tag: Option<String> },
+    #[error("cannot bump '{{{component}}}': pattern '{pattern}' pins it to {value}; use a pattern with '{{{component}}}' to leave this line")]
+    PinnedVersionComponent {
+        component: String,
+        value: u32,
+        pattern: String,
+    },
+    #[error("--push needs the remote, which --offline never contacts")]
+    OfflinePush,
     #[error("invalid rule '{input}': {reason}")]
     InvalidRule { input: String, reason: String },
+    #[error("invalid config '{path}'{}: {reason}", config_key(.key))]
+    InvalidConfig {
+        path: String,
+        key: String,
+        reason: String,
+    },
+    #[error("no pre-release found{} to promote", channel_suffix(.channel))]
+    PreReleaseNotFound { channel: Option<String> },
+    #[error("pre-release '{pre_release}' is not newer than the latest stable version '{stable}'")]
+    PromotionNotNewer { pre_release: String, stable: String },
+    #[error(
+        "cannot move pre-release back from channel '{current}' to '{requested}' \
+         (latest pre-release is '{tag}')"
+    )]
+    ChannelRegression {
+        requested: String,
+        current: String,
+        tag: String,
+    },
+    #[error("failed to sign tag with '{program}': {reason}")]
+    SigningFailed { program: String, reason: String },
+    #[error("invalid file target '{input}': {reason}")]
+    InvalidFileTarget { input: String, reason: String },
+    #[error("cannot write version to '{path}': {reason}")]
+    VersionFile { path: String, reason: String },
+    #[error("version '{0}' not found")]
+    VersionNotFound(String),
+    #[error("failed to update '{path}': {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("release '{0}' would be an empty commit; list the files to update with --file")]
+    EmptyRelease(String),
+    #[error("tag '{0}' already exists")]
+    TagExists(String),
+    #[error("branch '{0}' already exists")]
+    BranchExists(String),
+    #[error("stream '{0}' is not defined in the config file")]
+    StreamNotFound(String),
+    #[error("initial version '{input}' does not match pattern '{pattern}'")]
+    InvalidInitialVersion { input: String, pattern: String },
+}
+
+impl FlophaError {
+    /// A stable, machine-readable identifier of the error kind, used by `--format json`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            FlophaError::Git(_) => "git",
+            FlophaError::RepoNotFound { .. } => "repo_not_found",
+            FlophaError::RemoteNotFound { .. } => "remote_not_found",
+            FlophaError::PushRejected { .. } => "push_rejected",
+            FlophaError::MissingVersionComponent(_) => "missing_version_component",
+            FlophaError::ShallowHistory { .. } => "shallow_history",
+            FlophaError::PinnedVersionComponent { .. } => "pinned_version_component",
+            FlophaError::OfflinePush => "offline_push",
+            FlophaError::InvalidRule { .. } => "invalid_rule",
+            FlophaError::InvalidConfig { .. } => "invalid_config",
+            FlophaError::PreReleaseNotFound { .. } => "pre_release_not_found",
+            FlophaError::PromotionNotNewer { .. } => "promotion_not_newer",
+            FlophaError::ChannelRegression { .. } => "channel_regression",
+            FlophaError::SigningFailed { .. } => "signing_failed",
+            FlophaError::InvalidFileTarget { .. } => "invalid_file_target",
+            FlophaError::VersionFile { .. } => "version_file",
+            FlophaError::VersionNotFound(_) => "version_not_found",
+            FlophaError::Io { .. } => "io",
+            FlophaError::EmptyRelease(_) => "empty_release",
+            FlophaError::TagExists(_) => "tag_exists",
+            FlophaError::BranchExists(_) => "branch_exists",
+            FlophaError::StreamNotFound(_) => "stream_not_found",
+            FlophaError::InvalidInitialVersion { .. } => "invalid_initial_version",
+        }
+    }
+}
+
+fn channel_suffix(channel: &Option<String>) -> String {
+    match channel {
+        Some(channel) => format!(" on channel '{}'", channel),
+        None => String::new(),
+    }
+}
+
+fn since_tag(tag: &Option<String>) -> String {
+    match tag {
+        Some(tag) => format!(" since '{}'", tag),
+        None => String::new(),
+    }
+}
+
+fn config_key(key: &str) -> String {
+    if key.is_empty() {
+        String::new()
+    } else {
+        format!(" at key '{}'", key)
+    }
 }
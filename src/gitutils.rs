@@ -1,7 +1,7 @@
 use std::io::Write;
 use std::path::Path;
 
-use git2::{Branch, DescribeFormatOptions, DescribeOptions, Repository};
+use git2::{Branch, DiffOptions, Repository, StatusOptions};
 
 use crate::error::FlophaError;
 
@@ -22,7 +22,163 @@ pub fn get_remote<'a>(repo: &'a Repository, name: &str) -> Result<git2::Remote<'
 
 pub fn tag_oid(repo: &Repository, id: git2::Oid, tagname: &str) -> Result<git2::Oid, git2::Error> {
     let obj = repo.find_object(id, None)?;
-    repo.tag_lightweight(tagname, &obj, true)
+    repo.tag_lightweight(tagname, &obj, false)
+}
+
+/// Creates an annotated tag object for `id` with the configured user as tagger.
+///
+/// With `sign` the tag is signed like `git tag -s`, using the program selected by
+/// `gpg.format` and the key in `user.signingkey`.
+pub fn annotated_tag_oid(
+    repo: &Repository,
+    id: git2::Oid,
+    tagname: &str,
+    message: &str,
+    sign: bool,
+) -> Result<git2::Oid, FlophaError> {
+    let obj = repo.find_object(id, None)?;
+    let tagger = repo.signature()?;
+    // git always stores tag messages with a trailing newline; signatures are appended
+    // right after it.
+    let message = format!("{}\n", message.trim_end());
+    if !sign {
+        return Ok(repo.tag(tagname, &obj, &tagger, &message, false)?);
+    }
+    // libgit2 cannot sign, so write the unsigned tag object first and re-create it with
+    // the signature appended to its raw content.
+    let unsigned = repo.tag_annotation_create(tagname, &obj, &tagger, &message)?;
+    let odb = repo.odb()?;
+    let mut buffer = odb.read(unsigned)?.data().to_vec();
+    let signature = sign_buffer(&repo.config()?, &buffer, &tagger)?;
+    buffer.extend_from_slice(signature.as_bytes());
+    let signed = odb.write(git2::ObjectType::Tag, &buffer)?;
+    repo.reference(
+        &format!("refs/tags/{}", tagname),
+        signed,
+        false,
+        "flopha: signed tag",
+    )?;
+    Ok(signed)
+}
+
+/// Signs `buffer` with gpg, gpgsm or ssh-keygen depending on `gpg.format`, mirroring
+/// what git does for `git tag -s`. Returns the armored signature.
+fn sign_buffer(
+    config: &git2::Config,
+    buffer: &[u8],
+    tagger: &git2::Signature,
+) -> Result<String, FlophaError> {
+    let format = config
+        .get_string("gpg.format")
+        .unwrap_or_else(|_| "openpgp".to_string());
+    let key = config.get_string("user.signingkey").ok();
+    let mut key_file = None;
+    let (program, args) = match format.as_str() {
+        "openpgp" | "x509" => {
+            let program = if format == "openpgp" {
+                config
+                    .get_string("gpg.openpgp.program")
+                    .or_else(|_| config.get_string("gpg.program"))
+                    .unwrap_or_else(|_| "gpg".to_string())
+            } else {
+                config
+                    .get_string("gpg.x509.program")
+                    .unwrap_or_else(|_| "gpgsm".to_string())
+            };
+            // Without user.signingkey git picks the key matching the tagger identity.
+            let key = key.unwrap_or_else(|| {
+                format!(
+                    "{} <{}>",
+                    tagger.name().unwrap_or_default(),
+                    tagger.email().unwrap_or_default()
+                )
+            });
+            (program, vec!["-bsau".to_string(), key])
+        }
+        "ssh" => {
+            let program = config
+                .get_string("gpg.ssh.program")
+                .unwrap_or_else(|_| "ssh-keygen".to_string());
+            let key = key.ok_or_else(|| FlophaError::SigningFailed {
+                program: program.clone(),
+                reason: "user.signingkey is not set".to_string(),
+            })?;
+            let mut args = vec!["-Y", "sign", "-n", "git", "-f"]
+                .into_iter()
+                .map(str::to_string)
+                .collect::<Vec<_>>();
+            // A literal public key is signed through ssh-agent from a temporary key file.
+            let literal = key
+                .strip_prefix("key::")
+                .or_else(|| key.starts_with("ssh-").then_some(key.as_str()));
+            if let Some(literal) = literal {
+                let file = tempfile::Builder::new()
+                    .prefix("flopha-signingkey-")
+                    .suffix(".pub")
+                    .tempfile()
+                    .and_then(|mut file| file.write_all(literal.as_bytes()).map(|_| file))
+                    .map_err(|e| FlophaError::SigningFailed {
+                        program: program.clone(),
+                        reason: e.to_string(),
+                    })?;
+                args.push(file.path().display().to_string());
+                args.push("-U".to_string());
+                key_file = Some(file);
+            } else {
+                args.push(expand_home(&key));
+            }
+            (program, args)
+        }
+        other => {
+            return Err(FlophaError::SigningFailed {
+                program: other.to_string(),
+                reason: format!("unsupported gpg.format '{}'", other),
+            })
+        }
+    };
+
+    let output = run_signer(&program, &args, buffer);
+    // The temporary key file is removed when dropped.
+    drop(key_file);
+    let output = output.map_err(|reason| FlophaError::SigningFailed {
+        program: program.clone(),
+        reason,
+    })?;
+    log::debug!("Signed tag with '{}'", program);
+    Ok(output)
+}
+
+fn run_signer(program: &str, args: &[String], buffer: &[u8]) -> Result<String, String> {
+    let mut child = std::process::Command::new(program)
+        .args(args)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    child
+        .stdin
+        .take()
+        .ok_or_else(|| "failed to open stdin".to_string())?
+        .write_all(buffer)
+        .map_err(|e| e.to_string())?;
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(stderr.trim().to_string());
+    }
+    let signature = String::from_utf8(output.stdout).map_err(|e| e.to_string())?;
+    if signature.trim().is_empty() {
+        return Err("no signature produced".to_string());
+    }
+    Ok(signature)
+}
+
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{}/{}", home, rest),
+        _ => path.to_string(),
+    }
 }
 
 pub fn commit(repo: &Repository, message: &str) -> Result<git2::Oid, git2::Error> {
@@ -83,19 +239,116 @@ pub fn get_head_branch(repo: &Repository) -> Result<Branch<'_>, git2::Error> {
     Ok(Branch::wrap(repo.head()?))
 }
 
-pub fn get_last_tag_name(repo: &Repository) -> Result<String, git2::Error> {
-    let describe = repo.describe(DescribeOptions::new().describe_tags())?;
-    describe.format(Some(DescribeFormatOptions::new().abbreviated_size(0)))
+/// Returns true when `ancestor` is `commit` itself or one of its ancestors.
+pub fn is_ancestor(
+    repo: &Repository,
+    ancestor: git2::Oid,
+    commit: git2::Oid,
+) -> Result<bool, git2::Error> {
+    Ok(ancestor == commit || repo.graph_descendant_of(commit, ancestor)?)
+}
+
+/// Returns true when every commit from `to` back to `from`, or back to the root commits
+/// without `from`, is present: the walk never reaches a commit of a shallow clone whose
+/// parents were left out.
+pub fn history_is_complete(
+    repo: &Repository,
+    from: Option<git2::Oid>,
+    to: git2::Oid,
+) -> Result<bool, git2::Error> {
+    if !repo.is_shallow() {
+        return Ok(true);
+    }
+    let boundary: std::collections::HashSet<git2::Oid> =
+        std::fs::read_to_string(repo.path().join("shallow"))
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| git2::Oid::from_str(line.trim()).ok())
+            .collect();
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(to)?;
+    if let Some(from) = from {
+        revwalk.hide(from)?;
+    }
+    for oid in revwalk {
+        if boundary.contains(&oid?) {
+            return Ok(false);
+        }
+    }
+    Ok(true)
 }
 
+/// Returns true when tracked files have uncommitted changes, like `git describe --dirty`.
+/// Untracked files are ignored.
+pub fn is_dirty(repo: &Repository) -> Result<bool, git2::Error> {
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(false).include_ignored(false);
+    Ok(!repo.statuses(Some(&mut opts))?.is_empty())
+}
+
+/// Fetches the remote's branches into its remote-tracking refs (`refs/remotes/<remote>/*`)
+/// along with its tags. Local branches are never touched, so diverged local work is kept.
 pub fn fetch_all(remote: &mut git2::Remote) -> Result<(), git2::Error> {
     log::debug!("Fetching all branches and tags from remote...");
+    let refspec = tracking_refspec(remote)?;
     let mut fo = fetch_options();
-    remote.fetch(&["refs/heads/*:refs/heads/*"], Some(&mut fo), None)?;
+    remote.fetch(&[&refspec], Some(&mut fo), None)?;
     log::debug!("Successfully fetched all branches and tags from remote.");
     Ok(())
 }
 
+/// Deepens a shallow clone to `depth` commits from the remote's branch tips, or fetches
+/// its whole history with `None`.
+pub fn deepen(remote: &mut git2::Remote, depth: Option<i32>) -> Result<(), git2::Error> {
+    // GIT_FETCH_DEPTH_UNSHALLOW
+    const UNSHALLOW: i32 = i32::MAX;
+    log::debug!("Deepening shallow clone to {:?} commits...", depth);
+    let refspec = tracking_refspec(remote)?;
+    let mut fo = fetch_options();
+    fo.depth(depth.unwrap_or(UNSHALLOW));
+    remote.fetch(&[&refspec], Some(&mut fo), None)?;
+    Ok(())
+}
+
+/// The refspec fetching the remote's branches into `refs/remotes/<remote>/*`.
+fn tracking_refspec(remote: &git2::Remote) -> Result<String, git2::Error> {
+    let name = remote
+        .name()
+        .ok_or_else(|| git2::Error::from_str("remote name is not valid UTF-8"))?;
+    Ok(format!("+refs/heads/*:refs/remotes/{}/*", name))
+}
+
+/// Fetches only `refspecs` from the remote, without following other tags.
+pub fn fetch_refs(remote: &mut git2::Remote, refspecs: &[String]) -> Result<(), git2::Error> {
+    log::debug!("Fetching {} ref(s) from remote...", refspecs.len());
+    let mut fo = fetch_options();
+    fo.download_tags(git2::AutotagOption::None);
+    remote.fetch(refspecs, Some(&mut fo), None)?;
+    Ok(())
+}
+
+/// Lists the refs the remote advertises, like `git ls-remote`, as `(refname, commit)`
+/// pairs with annotated tags peeled to their commit. No objects are downloaded.
+pub fn list_remote_refs(
+    remote: &mut git2::Remote,
+) -> Result<Vec<(String, git2::Oid)>, git2::Error> {
+    log::debug!("Listing refs advertised by remote...");
+    let connection = remote.connect_auth(git2::Direction::Fetch, Some(git_callbacks()), None)?;
+    let mut refs: Vec<(String, git2::Oid)> = Vec::new();
+    for head in connection.list()? {
+        match head.name().strip_suffix("^{}") {
+            // The peeled commit of an annotated tag follows the tag itself.
+            Some(tag) => {
+                if let Some(last) = refs.last_mut().filter(|(name, _)| name == tag) {
+                    last.1 = head.oid();
+                }
+            }
+            None => refs.push((head.name().to_string(), head.oid())),
+        }
+    }
+    Ok(refs)
+}
+
 fn fetch_options() -> git2::FetchOptions<'static> {
     let mut fo = git2::FetchOptions::new();
     fo.download_tags(git2::AutotagOption::All);
@@ -131,9 +384,31 @@ pub fn push_branch(remote: &mut git2::Remote, branch: &mut Branch) -> Result<(),
     Ok(())
 }
 
+/// Pushes several references in one push, e.g. a branch and the tag on its tip.
+pub fn push_refs(remote: &mut git2::Remote, refnames: &[String]) -> Result<(), git2::Error> {
+    log::debug!("Pushing {} to remote...", refnames.join(", "));
+    let mut po = push_options();
+    let refspecs: Vec<String> = refnames
+        .iter()
+        .map(|name| format!("{}:{}", name, name))
+        .collect();
+    remote.push(&refspecs, Some(&mut po))?;
+    log::debug!("Successfully pushed {} to remote.", refnames.join(", "));
+    Ok(())
+}
+
 fn push_options() -> git2::PushOptions<'static> {
     let mut po = git2::PushOptions::new();
-    let cb = git_callbacks();
+    let mut cb = git_callbacks();
+    // libgit2 reports per-ref rejections (e.g. non-fast-forward, protected refs) through
+    // this callback only; without it a rejected push returns Ok(()).
+    cb.push_update_reference(|refname, status| match status {
+        Some(msg) => Err(git2::Error::from_str(&format!(
+            "remote rejected '{}': {}",
+            refname, msg
+        ))),
+        None => Ok(()),
+    });
     po.remote_callbacks(cb);
     po
 }
@@ -229,41 +504,54 @@ pub fn create_branch(repo: &Repository, name: &str, force: bool) -> Result<(), g
     Ok(())
 }
 
-/// Returns commit messages for every commit reachable from HEAD that was made
-/// *after* the given tag (i.e., not included in the tagged commit or its ancestors).
-pub fn commits_since_tag(repo: &Repository, tag_name: &str) -> Result<Vec<String>, git2::Error> {
-    let tag_obj = repo.revparse_single(&format!("refs/tags/{}", tag_name))?;
-    let tag_commit_oid = tag_obj.peel_to_commit()?.id();
-
-    let mut revwalk = repo.revwalk()?;
-    revwalk.push_head()?;
-    // hide() tells libgit2 to stop exploring at (and including) the tagged commit and
-    // all its ancestors. A simple break-on-match would silently walk the entire history
-    // on merge-heavy DAGs where the tag commit may not appear in the linear stream.
-    revwalk.hide(tag_commit_oid)?;
-    revwalk.set_sorting(git2::Sort::TOPOLOGICAL)?;
-
-    let mut messages = Vec::new();
-    for oid in revwalk {
-        let commit = repo.find_commit(oid?)?;
-        if let Some(msg) = commit.message() {
-            messages.push(msg.to_string());
+/// Returns true when `commit` changes a file matched by one of the `paths` pathspecs
+/// (a directory such as `api/` or a glob such as `*.rs`). An empty `paths` matches every commit.
+///
+/// Like `git log -- <path>`, a merge commit only counts when it differs from every
+/// parent, so merging unrelated work does not pull its changes into this scope.
+pub fn commit_touches_paths(
+    repo: &Repository,
+    commit: &git2::Commit,
+    paths: &[String],
+) -> Result<bool, git2::Error> {
+    if paths.is_empty() {
+        return Ok(true);
+    }
+    let mut opts = DiffOptions::new();
+    for path in paths {
+        opts.pathspec(path);
+    }
+    let tree = commit.tree()?;
+    if commit.parent_count() == 0 {
+        let diff = repo.diff_tree_to_tree(None, Some(&tree), Some(&mut opts))?;
+        return Ok(diff.deltas().len() > 0);
+    }
+    for parent in commit.parents() {
+        let diff = repo.diff_tree_to_tree(Some(&parent.tree()?), Some(&tree), Some(&mut opts))?;
+        if diff.deltas().len() == 0 {
+            return Ok(false);
         }
     }
-    Ok(messages)
+    Ok(true)
 }
 
-/// Returns the Unix timestamp (seconds) of the commit a tag points to.
-pub fn tag_commit_time(repo: &Repository, tag_name: &str) -> Result<i64, git2::Error> {
+/// Returns the Unix timestamp (seconds) of a tag: the tagger date of an annotated tag,
+/// or the date of the commit a lightweight tag points to.
+pub fn tag_time(repo: &Repository, tag_name: &str) -> Result<i64, git2::Error> {
     let tag_obj = repo.revparse_single(&format!("refs/tags/{}", tag_name))?;
+    if let Some(tagger) = tag_obj.as_tag().and_then(|tag| tag.tagger()) {
+        return Ok(tagger.when().seconds());
+    }
     Ok(tag_obj.peel_to_commit()?.time().seconds())
 }
 
-/// Counts commits reachable from `to_oid` that are NOT ancestors of `from_oid`.
+/// Counts commits reachable from `to_oid` that are NOT ancestors of `from_oid`,
+/// restricted to commits touching `paths` when it is non-empty.
 pub fn count_commits_between(
     repo: &Repository,
     from_oid: git2::Oid,
     to_oid: git2::Oid,
+    paths: &[String],
 ) -> Result<usize, git2::Error> {
     let mut revwalk = repo.revwalk()?;
     revwalk.push(to_oid)?;
@@ -271,7 +559,39 @@ pub fn count_commits_between(
     // reachable only through merged branches.
     revwalk.hide(from_oid)?;
     revwalk.set_sorting(git2::Sort::TOPOLOGICAL)?;
-    Ok(revwalk.count())
+    let mut count = 0;
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        if commit_touches_paths(repo, &commit, paths)? {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Returns the commits reachable from `to_oid` that are not ancestors of `from_oid`
+/// (the whole history when `from_oid` is `None`), newest first. When `paths` is
+/// non-empty only commits touching those paths are returned.
+pub fn commits_between<'r>(
+    repo: &'r Repository,
+    from_oid: Option<git2::Oid>,
+    to_oid: git2::Oid,
+    paths: &[String],
+) -> Result<Vec<git2::Commit<'r>>, git2::Error> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(to_oid)?;
+    if let Some(from_oid) = from_oid {
+        revwalk.hide(from_oid)?;
+    }
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL)?;
+    let mut commits = Vec::new();
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        if commit_touches_paths(repo, &commit, paths)? {
+            commits.push(commit);
+        }
+    }
+    Ok(commits)
 }
 
 /// Resolves a tag name to the OID of the commit it points to.
@@ -279,3 +599,24 @@ pub fn tag_commit_oid(repo: &Repository, tag_name: &str) -> Result<git2::Oid, gi
     let obj = repo.revparse_single(&format!("refs/tags/{}", tag_name))?;
     Ok(obj.peel_to_commit()?.id())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils;
+
+    #[test]
+    fn test_tag_time_prefers_tagger_date() {
+        let (_td, repo) = testutils::init_repo();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let tagger =
+            git2::Signature::new("name", "email", &git2::Time::new(1_000_000_000, 0)).unwrap();
+        repo.tag("v1.0.0", head.as_object(), &tagger, "v1.0.0", false)
+            .unwrap();
+        repo.tag_lightweight("v1.0.1", head.as_object(), false)
+            .unwrap();
+
+        assert_eq!(tag_time(&repo, "v1.0.0").unwrap(), 1_000_000_000);
+        assert_eq!(tag_time(&repo, "v1.0.1").unwrap(), head.time().seconds());
+    }
+}
@@ -1,9 +1,13 @@
 #[cfg(test)]
 mod testutils;
 
+pub mod changelog;
 pub mod cli;
+pub mod config;
 pub mod error;
 pub mod gitutils;
+pub mod output;
 pub mod service;
+pub mod version_files;
 pub mod version_source;
 pub mod versioning;
@@ -1,8 +1,12 @@
 use std::path::Path;
 
 use clap::{CommandFactory, Parser};
-use flopha::cli::{Cli, Commands};
-use flopha::service::{last_version, log_versions, next_version};
+use flopha::cli::{Cli, Commands, OutputFormat};
+use flopha::output;
+use flopha::service::{
+    changelog, current_version, last_version, list_streams, log_versions, next_version, promote,
+    release,
+};
 
 fn main() {
     let cli = Cli::parse();
@@ -17,9 +21,14 @@ fn main() {
 
     let path = Path::new(".");
     let result = match &cli.command {
-        Some(Commands::LastVersion(args)) => last_version(path, args),
-        Some(Commands::NextVersion(args)) => next_version(path, args),
-        Some(Commands::Log(args)) => log_versions(path, args).map(|_| None),
+        Some(Commands::LastVersion(args)) => last_version(path, &cli.global, args),
+        Some(Commands::NextVersion(args)) => next_version(path, &cli.global, args),
+        Some(Commands::CurrentVersion(args)) => current_version(path, &cli.global, args),
+        Some(Commands::Log(args)) => log_versions(path, &cli.global, args).map(|_| None),
+        Some(Commands::Release(args)) => release(path, &cli.global, args),
+        Some(Commands::Changelog(args)) => changelog(path, &cli.global, args).map(|_| None),
+        Some(Commands::Promote(args)) => promote(path, &cli.global, args),
+        Some(Commands::Streams) => list_streams(path, &cli.global).map(|_| None),
         None => {
             if cli.version {
                 println!("{}", env!("CARGO_PKG_VERSION"));
@@ -30,7 +39,10 @@ fn main() {
         }
     };
     if let Err(e) = result {
-        eprintln!("Error: {e}");
+        match cli.global.format {
+            OutputFormat::Text => eprintln!("Error: {e}"),
+            OutputFormat::Json => eprintln!("{}", output::error(&e)),
+        }
         std::process::exit(1);
     }
 }
@@ -0,0 +1,173 @@
+use serde_json::{json, Value};
+
+use crate::changelog::Release;
+use crate::cli::VersionSourceName;
+use crate::error::FlophaError;
+use crate::version_files::FileChange;
+use crate::versioning::{BumpReport, Version};
+
+/// Describes `version` for `--format json`: the version string, its parsed components,
+/// where it was read from and the commit it points to (if it exists yet).
+pub fn version(version: &Version, source: &VersionSourceName, commit: Option<git2::Oid>) -> Value {
+    json!({
+        "version": version.tag,
+        "major": version.component("major"),
+        "minor": version.component("minor"),
+        "patch": version.component("patch"),
+        "components": version
+            .components
+            .iter()
+            .map(|(name, value)| (name.clone(), json!(value)))
+            .collect::<serde_json::Map<_, _>>(),
+        "pre": version.pre_release(),
+        "build": version.build,
+        "source": source.as_str(),
+        "commit": commit.map(|oid| oid.to_string()),
+    })
+}
+
+/// Lists the files written by `--write-files` and whether each one changed.
+pub fn files(files: &[FileChange]) -> Value {
+    files
+        .iter()
+        .map(|file| json!({ "path": file.path.display().to_string(), "changed": file.changed }))
+        .collect()
+}
+
+/// Describes how `--auto` chose its bump for `--explain`: each commit, abbreviated as in
+/// `shas`, with the rule that decided it.
+pub fn explanation(report: &BumpReport, shas: &[String]) -> Value {
+    let commits: Vec<Value> = shas
+        .iter()
+        .zip(&report.commits)
+        .map(|(sha, commit)| {
+            json!({
+                "commit": sha,
+                "subject": commit.subject,
+                "rule": commit.rule.as_ref().map(|(pattern, increment)| {
+                    json!({ "pattern": pattern, "level": increment.to_string() })
+                }),
+            })
+        })
+        .collect();
+    json!({ "increment": report.increment.to_string(), "commits": commits })
+}
+
+/// The object printed when a command finds no matching version.
+pub fn no_version() -> Value {
+    json!({ "version": null })
+}
+
+pub fn changelog(releases: &[Release], markdown: &str) -> Value {
+    let releases: Vec<Value> = releases
+        .iter()
+        .map(|release| {
+            let entries: Vec<Value> = release
+                .entries
+                .iter()
+                .map(|entry| {
+                    json!({
+                        "section": entry.section.title(),
+                        "scope": entry.scope,
+                        "description": entry.description,
+                        "commit": entry.hash,
+                    })
+                })
+                .collect();
+            json!({
+                "version": if release.date.is_some() { Some(&release.title) } else { None },
+                "date": release.date,
+                "entries": entries,
+            })
+        })
+        .collect();
+    json!({ "releases": releases, "markdown": markdown })
+}
+
+/// Wraps `error` as `{"error": {"code": ..., "message": ...}}`; `code` never changes
+/// for a given kind of error, so scripts can match on it.
+pub fn error(error: &FlophaError) -> Value {
+    json!({
+        "error": {
+            "code": error.code(),
+            "message": error.to_string(),
+        }
+    })
+}
+
+pub fn print(value: &Value) {
+    println!("{}", value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::versioning::{conventional_bump_rules, detect_increment, parse_pre_release};
+
+    #[test]
+    fn test_version_lists_parsed_components() {
+        let version = Version::new("v1.2.3-rc.1".to_string(), Some(1), Some(2), Some(3))
+            .with_pre(parse_pre_release("rc.1"));
+        let oid = git2::Oid::from_str("0123456789abcdef0123456789abcdef01234567").unwrap();
+        assert_eq!(
+            super::version(&version, &VersionSourceName::Tag, Some(oid)),
+            json!({
+                "version": "v1.2.3-rc.1",
+                "major": 1,
+                "minor": 2,
+                "patch": 3,
+                "components": { "major": 1, "minor": 2, "patch": 3 },
+                "pre": "rc.1",
+                "build": null,
+                "source": "tag",
+                "commit": "0123456789abcdef0123456789abcdef01234567",
+            })
+        );
+    }
+
+    #[test]
+    fn test_explanation_lists_deciding_rules() {
+        let messages = vec![
+            "feat: search\n\nDetails".to_string(),
+            "docs: typo".to_string(),
+        ];
+        let report = detect_increment(&messages, &conventional_bump_rules());
+        let shas = vec!["3f2a9c1".to_string(), "a1b2c3d".to_string()];
+        assert_eq!(
+            explanation(&report, &shas),
+            json!({
+                "increment": "minor",
+                "commits": [
+                    {
+                        "commit": "3f2a9c1",
+                        "subject": "feat: search",
+                        "rule": { "pattern": "(?m)^feat(\\([^)]+\\))?:", "level": "minor" },
+                    },
+                    { "commit": "a1b2c3d", "subject": "docs: typo", "rule": null },
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn test_error_has_stable_code() {
+        let value = error(&FlophaError::TagExists("v1.0.0".to_string()));
+        assert_eq!(
+            value,
+            json!({ "error": { "code": "tag_exists", "message": "tag 'v1.0.0' already exists" } })
+        );
+    }
+
+    #[test]
+    fn test_error_message_includes_source() {
+        let value = error(&FlophaError::PushRejected {
+            name: "v1.0.0".to_string(),
+            remote: "origin".to_string(),
+            source: git2::Error::from_str("non-fast-forward"),
+        });
+        assert_eq!(
+            value["error"]["message"],
+            "failed to push 'v1.0.0' to remote 'origin': non-fast-forward"
+        );
+    }
+}
@@ -1,120 +1,864 @@
 use std::path::Path;
 
-use crate::cli::{LastVersionArgs, LogArgs, NextVersionArgs, VersionSourceName};
+use serde_json::json;
+
+use crate::changelog::{self, Entry, Release};
+use crate::cli::{
+    ChangelogArgs, CurrentVersionArgs, GlobalArgs, LastVersionArgs, LogArgs, NextVersionArgs,
+    OutputFormat, PromoteArgs, ReleaseArgs, VersionSourceName,
+};
+use crate::config::Config;
 use crate::error::FlophaError;
 use crate::gitutils;
-use crate::version_source::{BranchVersionSource, TagVersionSource, VersionSource};
-use crate::versioning::{self, BumpRule, Increment, Versioner};
-
-pub fn last_version(path: &Path, args: &LastVersionArgs) -> Result<Option<String>, FlophaError> {
+use crate::output;
+use crate::version_files::{self, FileChange, FileTarget};
+use crate::version_source::{
+    BranchVersionSource, RemoteVersionSource, TagOptions, TagVersionSource, VersionSource,
+};
+use crate::versioning::{self, BumpReport, BumpRule, CalendarDate, Increment, Version, Versioner};
+
+const DEFAULT_PATTERN: &str = "v{major}.{minor}.{patch}";
+
+pub fn last_version(
+    path: &Path,
+    global: &GlobalArgs,
+    args: &LastVersionArgs,
+) -> Result<Option<String>, FlophaError> {
     let repo = gitutils::get_repo(path)?;
-    let mut remote = gitutils::get_remote(&repo, "origin")?;
-    gitutils::fetch_all(&mut remote)?;
-    let pattern = args
-        .pattern
-        .clone()
-        .unwrap_or("v{major}.{minor}.{patch}".to_string());
-    let versioner = versioner_factory(&repo, pattern, &args.source);
+    let args = &load_config(&repo, global)?.last_version_args(args);
+    let pattern = args.pattern.clone().unwrap_or(DEFAULT_PATTERN.to_string());
+    let source = args.source.clone().unwrap_or_default();
+    refresh(&repo, global, &source)?;
+    // Without --reachable, --rev would not change the answer, so it implies it.
+    let reachable = args.reachable || args.rev.is_some();
+    let target = target_commit(&repo, args.rev.as_deref())?;
+    let version_source = version_source_factory(
+        &repo,
+        &source,
+        global,
+        TagOptions::default(),
+        target.filter(|_| reachable),
+    )?;
+    let versioner = Versioner::new(version_source.fetch_all(&repo), pattern);
     if let Some(version) = versioner.last_version() {
-        println!("{}", version.tag);
+        match global.format {
+            OutputFormat::Text => println!("{}", version.tag),
+            OutputFormat::Json => {
+                let commit = version_source.commit_id(&repo, &version.tag).ok();
+                output::print(&output::version(&version, &source, commit));
+            }
+        }
 
         if args.checkout {
-            let version_source = version_source_factory(&args.source);
             version_source.checkout(&repo, &version.tag)?;
         }
 
         Ok(Some(version.tag))
     } else {
-        println!("No version found");
+        print_no_version(global);
         Ok(None)
     }
 }
 
-pub fn next_version(path: &Path, args: &NextVersionArgs) -> Result<Option<String>, FlophaError> {
+const DEFAULT_DEV_TEMPLATE: &str = "{next}-dev.{distance}+g{sha}{dirty}";
+
+/// Describes HEAD like `git describe`: the last matching version reachable from HEAD when
+/// HEAD is exactly on it and the working tree is clean, otherwise a development version
+/// rendered from the template.
+pub fn current_version(
+    path: &Path,
+    global: &GlobalArgs,
+    args: &CurrentVersionArgs,
+) -> Result<Option<String>, FlophaError> {
     let repo = gitutils::get_repo(path)?;
-    let mut remote = gitutils::get_remote(&repo, "origin")?;
-    gitutils::fetch_all(&mut remote)?;
+    let args = &load_config(&repo, global)?.current_version_args(args);
+    let pattern = args.pattern.clone().unwrap_or(DEFAULT_PATTERN.to_string());
+    let source = args.source.clone().unwrap_or_default();
+    refresh(&repo, global, &source)?;
+    let version_source =
+        version_source_factory(&repo, &source, global, TagOptions::default(), None)?;
+    let versioner = Versioner::new(version_source.fetch_all(&repo), pattern.clone());
+
+    let head_ref = repo.head()?;
+    let head = head_ref.peel_to_commit()?.id();
+    let branch = match head_ref.shorthand() {
+        Some(name) if head_ref.is_branch() => name.to_string(),
+        _ => "HEAD".to_string(),
+    };
+    let versions = versioner.all_versions();
+    let tags: Vec<String> = versions.iter().map(|v| v.tag.clone()).collect();
+    version_source.fetch_versions(&repo, &tags)?;
+    let mut base = None;
+    for version in versions.into_iter().rev() {
+        let commit = version_source.commit_id(&repo, &version.tag)?;
+        if gitutils::is_ancestor(&repo, commit, head)? {
+            base = Some((version, commit));
+            break;
+        }
+    }
+    let Some((base, base_commit)) = base else {
+        print_no_version(global);
+        return Ok(None);
+    };
+
+    let distance = gitutils::count_commits_between(&repo, base_commit, head, &[])?;
+    let dirty = gitutils::is_dirty(&repo)?;
+    let current = if distance == 0 && !dirty {
+        base.tag.clone()
+    } else {
+        let template = args
+            .template
+            .clone()
+            .unwrap_or(DEFAULT_DEV_TEMPLATE.to_string());
+        let next = if template.contains("{next}") {
+            Versioner::new(vec![base.tag.clone()], pattern)
+                .next_version(Increment::Patch)?
+                .map(|v| v.tag)
+                .unwrap_or_default()
+        } else {
+            String::new()
+        };
+        let sha = repo.find_object(head, None)?.short_id()?;
+        template
+            .replace("{version}", &base.tag)
+            .replace("{next}", &next)
+            .replace("{distance}", &distance.to_string())
+            .replace("{sha}", sha.as_str().unwrap_or_default())
+            .replace("{dirty}", if dirty { ".dirty" } else { "" })
+            .replace("{branch}", &identifier_safe(&branch))
+    };
+
+    match global.format {
+        OutputFormat::Text => println!("{}", current),
+        OutputFormat::Json => output::print(&json!({
+            "version": current,
+            "base": base.tag,
+            "distance": distance,
+            "commit": head.to_string(),
+            "dirty": dirty,
+            "branch": branch,
+        })),
+    }
+    Ok(Some(current))
+}
+
+/// Replaces characters that are not allowed in SemVer identifiers, e.g. `feature/login`
+/// becomes `feature-login`.
+fn identifier_safe(s: &str) -> String {
+    s.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+pub fn next_version(
+    path: &Path,
+    global: &GlobalArgs,
+    args: &NextVersionArgs,
+) -> Result<Option<String>, FlophaError> {
+    let repo = gitutils::get_repo(path)?;
+    let args = &load_config(&repo, global)?.next_version_args(args);
+    check_push(global, args.create && args.push)?;
     let pattern = args
+        .version
         .pattern
         .clone()
-        .unwrap_or("v{major}.{minor}.{patch}".to_string());
+        .unwrap_or(DEFAULT_PATTERN.to_string());
+
+    let source = args.version.source.clone().unwrap_or_default();
+    refresh(&repo, global, &source)?;
+    let target = target_commit(&repo, args.rev.as_deref())?;
+    let version_source = version_source_factory(
+        &repo,
+        &source,
+        global,
+        tag_options(args),
+        target.filter(|_| args.version.reachable),
+    )?;
+    let versioner = Versioner::new(version_source.fetch_all(&repo), pattern.clone())
+        .with_initial(args.version.initial.as_deref())?;
+
+    let file_targets = parse_file_targets(&args.file)?;
+    if !args.write_files && !file_targets.is_empty() {
+        log::warn!("--file has no effect without --write-files");
+    }
 
-    let version_source = version_source_factory(&args.source);
-    let versioner = Versioner::new(version_source.fetch_all(&repo), pattern);
+    let (next, explanation) = compute_next_version(
+        &repo,
+        global,
+        args,
+        version_source.as_ref(),
+        &versioner,
+        &pattern,
+        target,
+    )?;
+    let next = match next {
+        Some(v) => v,
+        None => {
+            print_no_version(global);
+            return Ok(None);
+        }
+    };
+    if args.create
+        && repo
+            .find_reference(&version_source.refname(&next.tag))
+            .is_ok()
+    {
+        return Err(FlophaError::TagExists(next.tag));
+    }
+
+    if global.format == OutputFormat::Text {
+        println!("{}", next.tag);
+        if let Some(explanation) = &explanation {
+            print_explanation(explanation);
+        }
+    }
+
+    let mut files = Vec::new();
+    if args.write_files {
+        let root = repo.workdir().unwrap_or(path);
+        let bare = versioner.bare_version(&next);
+        files = version_files::write_version(root, &file_targets, &bare)?;
+        report_files(global, &files, &bare);
+    }
+
+    if args.create {
+        let target = match target {
+            Some(target) => target,
+            None => repo.head()?.peel_to_commit()?.id(),
+        };
+        version_source.create(&repo, &next.tag, target)?;
+        if args.push {
+            publish_version(&repo, version_source.as_ref(), &global.remote, &next.tag)?;
+        }
+    }
+
+    if global.format == OutputFormat::Json {
+        // The commit the new version points to, or would point to without --create.
+        let mut value = output::version(&next, &source, target);
+        value["previous"] = json!(versioner.last_version().map(|v| v.tag));
+        value["created"] = json!(args.create);
+        value["pushed"] = json!(args.create && args.push);
+        value["files"] = output::files(&files);
+        if let Some(explanation) = &explanation {
+            value["explain"] = output::explanation(&explanation.report, &explanation.shas);
+        }
+        output::print(&value);
+    }
+
+    Ok(Some(next.tag))
+}
+
+const DEFAULT_RELEASE_MESSAGE: &str = "chore(release): {version}";
+
+/// Writes the next version into the project files, commits them (with anything already
+/// staged) as the release commit and tags that commit, optionally pushing the branch
+/// and the tag together. A release that would commit nothing is refused.
+///
+/// Reads the version and file settings of `[next-version]`. When a step fails, HEAD,
+/// the index and the written files are restored and the new tag or branch is removed;
+/// a push the remote partially accepted cannot be undone from here.
+pub fn release(
+    path: &Path,
+    global: &GlobalArgs,
+    args: &ReleaseArgs,
+) -> Result<Option<String>, FlophaError> {
+    run_release(path, global, args).map(|(tag, _)| tag)
+}
+
+/// Runs [`release`], also returning how --auto decided the bump when --explain is set.
+fn run_release(
+    path: &Path,
+    global: &GlobalArgs,
+    args: &ReleaseArgs,
+) -> Result<(Option<String>, Option<Explanation>), FlophaError> {
+    let repo = gitutils::get_repo(path)?;
+    let config = load_config(&repo, global)?;
+    let commit_message = args
+        .commit_message
+        .clone()
+        .or(config.release.commit_message.clone())
+        .unwrap_or(DEFAULT_RELEASE_MESSAGE.to_string());
+    let args = &config.next_version_args(&args.next_version_args());
+    check_push(global, args.push)?;
+    let pattern = args
+        .version
+        .pattern
+        .clone()
+        .unwrap_or(DEFAULT_PATTERN.to_string());
+
+    let source = args.version.source.clone().unwrap_or_default();
+    refresh(&repo, global, &source)?;
+    let target = target_commit(&repo, None)?;
+    let version_source = version_source_factory(
+        &repo,
+        &source,
+        global,
+        tag_options(args),
+        target.filter(|_| args.version.reachable),
+    )?;
+    let versioner = Versioner::new(version_source.fetch_all(&repo), pattern.clone())
+        .with_initial(args.version.initial.as_deref())?;
+    let file_targets = parse_file_targets(&args.file)?;
+
+    let (next, explanation) = compute_next_version(
+        &repo,
+        global,
+        args,
+        version_source.as_ref(),
+        &versioner,
+        &pattern,
+        target,
+    )?;
+    let next = match next {
+        Some(v) => v,
+        None => {
+            print_no_version(global);
+            return Ok((None, explanation));
+        }
+    };
+    let refname = version_source.refname(&next.tag);
+    if repo.find_reference(&refname).is_ok() {
+        return Err(FlophaError::TagExists(next.tag));
+    }
+
+    let head = repo.head()?;
+    let prior = ReleaseState {
+        head_ref: head
+            .is_branch()
+            .then(|| head.name().map(str::to_string))
+            .flatten(),
+        head: head.peel_to_commit()?.id(),
+        index: repo.index()?.write_tree()?,
+    };
+    if args.push && prior.head_ref.is_none() {
+        return Err(git2::Error::from_str(
+            "HEAD is detached, check out a branch to push a release",
+        )
+        .into());
+    }
+    let root = repo
+        .workdir()
+        .ok_or_else(|| git2::Error::from_str("cannot release from a bare repository"))?
+        .to_path_buf();
+
+    let bare = versioner.bare_version(&next);
+    let files = version_files::write_version(&root, &file_targets, &bare)?;
+    report_files(global, &files, &bare);
+
+    let message = commit_message.replace("{version}", &next.tag);
+    let commit = commit_release(&repo, version_source.as_ref(), &next, &message, &files).and_then(
+        |commit| {
+            if args.push {
+                let mut remote = gitutils::get_remote(&repo, &global.remote)?;
+                let branch = prior.head_ref.clone().unwrap_or_default();
+                gitutils::push_refs(&mut remote, &[branch, refname.clone()]).map_err(|e| {
+                    FlophaError::PushRejected {
+                        name: next.tag.clone(),
+                        remote: global.remote.clone(),
+                        source: e,
+                    }
+                })?;
+            }
+            Ok(commit)
+        },
+    );
+    let commit = match commit {
+        Ok(commit) => commit,
+        Err(e) => {
+            rollback_release(&repo, &prior, &refname, &root, &files);
+            return Err(e);
+        }
+    };
+
+    match global.format {
+        OutputFormat::Text => {
+            println!("{}", next.tag);
+            if let Some(explanation) = &explanation {
+                print_explanation(explanation);
+            }
+        }
+        OutputFormat::Json => {
+            let mut value = output::version(&next, &source, Some(commit));
+            value["previous"] = json!(versioner.last_version().map(|v| v.tag));
+            value["pushed"] = json!(args.push);
+            value["files"] = output::files(&files);
+            if let Some(explanation) = &explanation {
+                value["explain"] = output::explanation(&explanation.report, &explanation.shas);
+            }
+            output::print(&value);
+        }
+    }
+
+    Ok((Some(next.tag), explanation))
+}
+
+/// Where HEAD and the index were before a release started.
+struct ReleaseState {
+    /// The branch HEAD was on, or `None` when it was detached.
+    head_ref: Option<String>,
+    head: git2::Oid,
+    index: git2::Oid,
+}
+
+/// Stages the changed files, commits them on HEAD and creates the version on that commit.
+fn commit_release(
+    repo: &git2::Repository,
+    version_source: &dyn VersionSource,
+    version: &Version,
+    message: &str,
+    files: &[FileChange],
+) -> Result<git2::Oid, FlophaError> {
+    let mut index = repo.index()?;
+    for file in files.iter().filter(|file| file.changed) {
+        index.add_path(&file.path)?;
+    }
+    index.write()?;
+    if index.write_tree()? == repo.head()?.peel_to_tree()?.id() {
+        return Err(FlophaError::EmptyRelease(version.tag.clone()));
+    }
+    let commit = gitutils::commit(repo, message)?;
+    log::debug!("Created release commit {}", commit);
+    version_source.create(repo, &version.tag, commit)?;
+    Ok(commit)
+}
+
+/// Undoes a failed release as far as possible, logging what could not be restored.
+fn rollback_release(
+    repo: &git2::Repository,
+    prior: &ReleaseState,
+    refname: &str,
+    root: &Path,
+    files: &[FileChange],
+) {
+    let result = (|| -> Result<(), FlophaError> {
+        // A branch version source checks out the new branch, so move HEAD back first.
+        match &prior.head_ref {
+            Some(head_ref) => repo.set_head(head_ref)?,
+            None => repo.set_head_detached(prior.head)?,
+        }
+        if let Ok(mut reference) = repo.find_reference(refname) {
+            reference.delete()?;
+        }
+        repo.reset(
+            &repo.find_object(prior.head, None)?,
+            git2::ResetType::Soft,
+            None,
+        )?;
+        let mut index = repo.index()?;
+        index.read_tree(&repo.find_tree(prior.index)?)?;
+        index.write()?;
+        version_files::restore(root, files)
+    })();
+    match result {
+        Ok(()) => log::debug!("Rolled back the release"),
+        Err(e) => log::warn!("Failed to roll back the release: {}", e),
+    }
+}
+
+/// The commits read by `--auto`, as abbreviated SHAs, next to the report on them.
+struct Explanation {
+    shas: Vec<String>,
+    report: BumpReport,
+}
+
+/// Computes the version following the last one matching `pattern` as of the commit
+/// `target` (HEAD or `--rev`), honouring --auto and --pre. Returns `None` when no
+/// version matches, along with how --auto decided the bump when --explain is set.
+fn compute_next_version(
+    repo: &git2::Repository,
+    global: &GlobalArgs,
+    args: &NextVersionArgs,
+    version_source: &dyn VersionSource,
+    versioner: &Versioner,
+    pattern: &str,
+    target: Option<git2::Oid>,
+) -> Result<(Option<Version>, Option<Explanation>), FlophaError> {
+    if !args.version.auto && !args.version.rule.is_empty() {
+        log::warn!("--rule has no effect without --auto");
+    }
+    if !args.version.auto && args.version.explain {
+        log::warn!("--explain has no effect without --auto");
+    }
 
     // Determine increment level, honouring --auto if set.
-    let increment = if args.auto {
-        let rules = build_rules(&args.rule)?;
-        match versioner.last_version() {
+    let mut explanation = None;
+    let increment = if args.version.auto {
+        let rules = build_rules(&args.version.rule)?;
+        let commits = match versioner.last_version() {
             Some(last) => {
-                let messages = gitutils::commits_since_tag(&repo, &last.tag).unwrap_or_default();
-                versioning::detect_increment(&messages, &rules)
+                version_source.fetch_versions(repo, std::slice::from_ref(&last.tag))?;
+                match target {
+                    Some(target) => {
+                        ensure_history(repo, global, version_source, Some(&last.tag), target)?;
+                        let from = version_source.commit_id(repo, &last.tag)?;
+                        Some(gitutils::commits_between(
+                            repo,
+                            Some(from),
+                            target,
+                            &args.version.path,
+                        )?)
+                    }
+                    None => Some(Vec::new()),
+                }
+            }
+            // Bumping from --initial: every commit so far goes into the first release.
+            None if args.version.initial.is_some() => Some(match target {
+                Some(target) => {
+                    ensure_history(repo, global, version_source, None, target)?;
+                    gitutils::commits_between(repo, None, target, &args.version.path)?
+                }
+                None => Vec::new(),
+            }),
+            None => None,
+        };
+        match commits {
+            Some(commits) => {
+                let messages: Vec<String> = commits
+                    .iter()
+                    .map(|commit| commit.message().unwrap_or_default().to_string())
+                    .collect();
+                let report = versioning::detect_increment(&messages, &rules);
+                let increment = report.increment.clone();
+                if args.version.explain {
+                    let shas = commits
+                        .iter()
+                        .map(|commit| {
+                            let id = commit.as_object().short_id()?;
+                            Ok(id.as_str().unwrap_or_default().to_string())
+                        })
+                        .collect::<Result<_, git2::Error>>()?;
+                    explanation = Some(Explanation { shas, report });
+                }
+                increment
             }
             None => {
                 log::warn!("--auto: no prior tag found, falling back to --increment");
-                args.increment.clone()
+                args.version.increment.clone().unwrap_or_default()
             }
         }
     } else {
-        args.increment.clone()
+        args.version.increment.clone().unwrap_or_default()
     };
 
-    let next = match versioner.next_version(increment)? {
-        Some(v) => v,
-        None => {
-            println!("No version found");
-            return Ok(None);
+    let Some(next) = versioner.next_version(increment)? else {
+        return Ok((None, explanation));
+    };
+
+    // If a pre-release channel was requested, compute the pre-release version.
+    match &args.version.pre {
+        Some(channel) => {
+            let tags = match args.version.source {
+                Some(VersionSourceName::Remote) => version_source.fetch_all(repo),
+                _ => TagVersionSource::default()
+                    .reachable_from(target.filter(|_| args.version.reachable))
+                    .fetch_all(repo),
+            };
+            let next = pre_release_version(
+                &next,
+                channel,
+                &args.version.pre_channel_order,
+                pattern,
+                tags,
+            )?;
+            Ok((Some(next), explanation))
         }
+        None => Ok((Some(next), explanation)),
+    }
+}
+
+/// Commits fetched by the first deepening of a shallow clone; each retry doubles it.
+const DEEPEN_DEPTH: i32 = 64;
+const MAX_DEEPEN_DEPTH: i32 = 1 << 16;
+
+/// Makes sure a shallow clone holds every commit `--auto` reads: those of `target` since
+/// the commit of the version `tag` in `version_source`, or its whole history without
+/// `tag`. The history is deepened step by step, or fetched whole with `--unshallow`; a
+/// clone that can't be deepened is an error rather than a bump guessed from the commits
+/// at hand.
+fn ensure_history(
+    repo: &git2::Repository,
+    global: &GlobalArgs,
+    version_source: &dyn VersionSource,
+    tag: Option<&str>,
+    target: git2::Oid,
+) -> Result<(), FlophaError> {
+    let complete = |repo: &git2::Repository| {
+        let from = tag
+            .map(|tag| version_source.commit_id(repo, tag))
+            .transpose();
+        !repo.is_shallow()
+            || from
+                .and_then(|from| gitutils::history_is_complete(repo, from, target))
+                .unwrap_or(false)
     };
+    if complete(repo) {
+        return Ok(());
+    }
+    log::debug!(
+        "Shallow clone: the history of {} since {} is incomplete",
+        target,
+        tag.unwrap_or("the root commits")
+    );
+    if !global.no_fetch && !global.offline {
+        let mut remote = gitutils::get_remote(repo, &global.remote)?;
+        let depths: Vec<Option<i32>> = if global.unshallow {
+            vec![None]
+        } else {
+            std::iter::successors(Some(DEEPEN_DEPTH), |depth| {
+                (*depth < MAX_DEEPEN_DEPTH).then(|| depth * 2)
+            })
+            .map(Some)
+            .collect()
+        };
+        for depth in depths {
+            gitutils::deepen(&mut remote, depth)?;
+            if complete(repo) {
+                return Ok(());
+            }
+        }
+    }
+    Err(FlophaError::ShallowHistory {
+        tag: tag.map(str::to_string),
+    })
+}
 
-    // If a pre-release channel was requested, compute the pre-release tag.
-    let final_tag = if let Some(channel) = &args.pre {
-        pre_release_tag(&next.tag, channel, &repo)
-    } else {
-        next.tag.clone()
+fn tag_options(args: &NextVersionArgs) -> TagOptions {
+    TagOptions {
+        annotate: args.annotate,
+        message: args.message.clone(),
+        sign: args.sign,
+    }
+}
+
+fn parse_file_targets(specs: &[String]) -> Result<Vec<FileTarget>, FlophaError> {
+    let targets = specs
+        .iter()
+        .map(|spec| FileTarget::parse(spec))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(targets)
+}
+
+/// Lists the files written by --write-files on stderr, so `$(flopha next-version)`
+/// still captures only the version.
+fn report_files(global: &GlobalArgs, files: &[FileChange], version: &str) {
+    if files.is_empty() {
+        log::warn!("No files to update, list them with --file");
+    }
+    if global.format != OutputFormat::Text {
+        return;
+    }
+    for file in files {
+        if file.changed {
+            eprintln!("Updated {}", file.path.display());
+        } else {
+            eprintln!("{} is already at {}", file.path.display(), version);
+        }
+    }
+}
+
+/// Prints on stderr, under the version, each commit `--auto` read with the rule that
+/// decided it, then the resulting bump.
+fn print_explanation(explanation: &Explanation) {
+    let commits: Vec<_> = explanation
+        .shas
+        .iter()
+        .zip(&explanation.report.commits)
+        .collect();
+    let width = commits
+        .iter()
+        .map(|(_, commit)| commit.subject.chars().count())
+        .max()
+        .unwrap_or(0);
+    for (sha, commit) in commits {
+        let decision = match &commit.rule {
+            Some((pattern, increment)) => format!("{:<5}  {}", increment.to_string(), pattern),
+            None => "-".to_string(),
+        };
+        eprintln!(
+            "  {}  {:<width$}  {}",
+            sha,
+            commit.subject,
+            decision,
+            width = width
+        );
+    }
+    eprintln!("Bump: {}", explanation.report.increment);
+}
+
+fn print_no_version(global: &GlobalArgs) {
+    match global.format {
+        OutputFormat::Text => println!("No version found"),
+        OutputFormat::Json => output::print(&output::no_version()),
+    }
+}
+
+/// Reads `flopha.toml` from the repository's working directory, if there is one, and
+/// applies the stream selected with `--stream`.
+fn load_config(repo: &git2::Repository, global: &GlobalArgs) -> Result<Config, FlophaError> {
+    let mut config = match repo.workdir() {
+        Some(dir) => Config::load(dir)?,
+        None => Config::default(),
     };
+    if let Some(stream) = &global.stream {
+        config.select_stream(stream)?;
+    }
+    Ok(config)
+}
 
-    println!("{}", final_tag);
+/// Opens the repository at `path` and refreshes its refs from the configured remote.
+fn open_repo(path: &Path, global: &GlobalArgs) -> Result<git2::Repository, FlophaError> {
+    let repo = gitutils::get_repo(path)?;
+    refresh(&repo, global, &VersionSourceName::Tag)?;
+    Ok(repo)
+}
 
-    if args.create {
-        version_source.create(&repo, &final_tag)?;
+/// Fetches the configured remote's branches and tags, unless versions are read from the
+/// remote itself: `--source remote` lists its refs and only fetches what it needs.
+fn refresh(
+    repo: &git2::Repository,
+    global: &GlobalArgs,
+    source: &VersionSourceName,
+) -> Result<(), FlophaError> {
+    if global.no_fetch || global.offline {
+        log::debug!("Skipping fetch (--no-fetch or --offline)");
+        return Ok(());
+    }
+    if let VersionSourceName::Remote = source {
+        log::debug!("Skipping fetch (--source remote)");
+        return Ok(());
+    }
+    let mut remote = gitutils::get_remote(repo, &global.remote)?;
+    gitutils::fetch_all(&mut remote)?;
+    Ok(())
+}
+
+/// Refuses to push with `--offline`, before anything is created.
+fn check_push(global: &GlobalArgs, push: bool) -> Result<(), FlophaError> {
+    if global.offline && push {
+        return Err(FlophaError::OfflinePush);
     }
+    Ok(())
+}
 
-    Ok(Some(final_tag))
+/// Pushes a freshly created tag or branch to `remote_name`.
+fn publish_version(
+    repo: &git2::Repository,
+    version_source: &dyn VersionSource,
+    remote_name: &str,
+    version: &str,
+) -> Result<(), FlophaError> {
+    let mut remote = gitutils::get_remote(repo, remote_name)?;
+    version_source
+        .publish(repo, &mut remote, version)
+        .map_err(|e| FlophaError::PushRejected {
+            name: version.to_string(),
+            remote: remote_name.to_string(),
+            source: e,
+        })
 }
 
-/// Returns the next pre-release tag for `base_version` on `channel`.
+/// Tags the commit of the latest pre-release with its stable version.
 ///
-/// Always scans the repo's actual git tags (not the version-source list, which
-/// can be branch names when --source=branch is used) so the counter is correct
-/// regardless of which version source drives the base version.
-fn pre_release_tag(base_version: &str, channel: &str, repo: &git2::Repository) -> String {
-    let prefix = format!("{}-{}.", base_version, channel);
-    let max_pre = repo
-        .tag_names(None)
-        .map(|names| {
-            names
-                .iter()
-                .flatten()
-                .filter_map(|t| t.strip_prefix(&prefix))
-                .filter_map(|s| s.parse::<u32>().ok())
-                .max()
-                .unwrap_or(0)
-        })
-        .unwrap_or(0);
-    format!("{}-{}.{}", base_version, channel, max_pre.saturating_add(1))
+/// The pre-release must be newer than the latest stable version, and the stable tag
+/// must not exist yet; HEAD is never tagged.
+pub fn promote(
+    path: &Path,
+    global: &GlobalArgs,
+    args: &PromoteArgs,
+) -> Result<Option<String>, FlophaError> {
+    let repo = open_repo(path, global)?;
+    let args = &load_config(&repo, global)?.promote_args(args);
+    check_push(global, args.push)?;
+    let pattern = args.pattern.clone().unwrap_or(DEFAULT_PATTERN.to_string());
+
+    let version_source = TagVersionSource::new(TagOptions {
+        annotate: args.annotate,
+        message: args.message.clone(),
+        sign: args.sign,
+    });
+    let tags = version_source.fetch_all(&repo);
+    let pre_versioner = Versioner::new(tags.clone(), versioning::pre_release_pattern(&pattern));
+    let channel = args.pre.as_deref().map(versioning::Identifier::parse);
+    let pre_release = pre_versioner
+        .all_versions()
+        .into_iter()
+        .rfind(|v| v.is_pre_release() && (channel.is_none() || v.pre.first() == channel.as_ref()))
+        .ok_or_else(|| FlophaError::PreReleaseNotFound {
+            channel: args.pre.clone(),
+        })?;
+
+    let release = pre_versioner.release_of(&pre_release);
+    if repo
+        .find_reference(&format!("refs/tags/{}", release.tag))
+        .is_ok()
+    {
+        return Err(FlophaError::TagExists(release.tag));
+    }
+    if let Some(stable) = Versioner::new(tags, pattern).last_version() {
+        if stable.cmp_precedence(&pre_release).is_ge() {
+            return Err(FlophaError::PromotionNotNewer {
+                pre_release: pre_release.tag,
+                stable: stable.tag,
+            });
+        }
+    }
+
+    let target = gitutils::tag_commit_oid(&repo, &pre_release.tag)?;
+    version_source.create(&repo, &release.tag, target)?;
+    if global.format == OutputFormat::Text {
+        println!("{}", release.tag);
+    }
+
+    if args.push {
+        publish_version(&repo, &version_source, &global.remote, &release.tag)?;
+    }
+
+    if global.format == OutputFormat::Json {
+        let mut value = output::version(&release, &VersionSourceName::Tag, Some(target));
+        value["pre_release"] = json!(pre_release.tag);
+        value["pushed"] = json!(args.push);
+        output::print(&value);
+    }
+
+    Ok(Some(release.tag))
+}
+
+/// Returns the next `<channel>.<n>` pre-release of `next`, or of the pre-release still in
+/// flight when that one is higher, counting on from the pre-releases among `tags`.
+///
+/// `tags` are the git tags (those reachable from the target with --reachable, or the
+/// remote's with --source remote) rather than the version-source list, which holds
+/// branch names with --source branch. Channels move along `channel_order`, or alpha, beta
+/// and rc when it is empty, and moving back to an earlier channel is an error.
+fn pre_release_version(
+    next: &Version,
+    channel: &str,
+    channel_order: &[String],
+    pattern: &str,
+    tags: Vec<String>,
+) -> Result<Version, FlophaError> {
+    let versioner = Versioner::new(tags, versioning::pre_release_pattern(pattern));
+    let channel_order: Vec<String> = if channel_order.is_empty() {
+        versioning::DEFAULT_CHANNEL_ORDER
+            .iter()
+            .map(|c| c.to_string())
+            .collect()
+    } else {
+        channel_order.to_vec()
+    };
+    versioner.next_pre_release(next, channel, &channel_order)
 }
 
-pub fn log_versions(path: &Path, args: &LogArgs) -> Result<(), FlophaError> {
+pub fn log_versions(path: &Path, global: &GlobalArgs, args: &LogArgs) -> Result<(), FlophaError> {
     let repo = gitutils::get_repo(path)?;
-    let mut remote = gitutils::get_remote(&repo, "origin")?;
-    gitutils::fetch_all(&mut remote)?;
+    let args = &load_config(&repo, global)?.log_args(args);
 
-    let pattern = args
-        .pattern
-        .clone()
-        .unwrap_or("v{major}.{minor}.{patch}".to_string());
-    let versioner = versioner_factory(&repo, pattern, &args.source);
+    let pattern = args.pattern.clone().unwrap_or(DEFAULT_PATTERN.to_string());
+    let source = args.source.clone().unwrap_or_default();
+    refresh(&repo, global, &source)?;
+    let version_source =
+        version_source_factory(&repo, &source, global, TagOptions::default(), None)?;
+    let versioner = Versioner::new(version_source.fetch_all(&repo), pattern);
 
     let mut versions = versioner.all_versions();
     // Show newest first.
@@ -123,40 +867,70 @@ pub fn log_versions(path: &Path, args: &LogArgs) -> Result<(), FlophaError> {
     if let Some(limit) = args.limit {
         versions.truncate(limit);
     }
+    let tags: Vec<String> = versions.iter().map(|v| v.tag.clone()).collect();
+    version_source.fetch_versions(&repo, &tags)?;
 
     if versions.is_empty() {
-        println!("No versions found");
+        match global.format {
+            OutputFormat::Text => println!("No versions found"),
+            OutputFormat::Json => output::print(&json!([])),
+        }
         return Ok(());
     }
 
-    // Collect display rows: (tag, date_str, commit_count_str)
-    let mut rows: Vec<(String, String, String)> = Vec::new();
+    // Collect rows: (version, tag time, commit count since the previous version)
+    let mut rows: Vec<(&Version, Option<i64>, Option<usize>)> = Vec::new();
     for (i, version) in versions.iter().enumerate() {
-        let date_str = gitutils::tag_commit_time(&repo, &version.tag)
-            .map(format_date)
-            .unwrap_or_else(|_| "unknown".to_string());
+        let time = gitutils::tag_time(&repo, &version.tag).ok();
 
         // Count commits between this version and the next older one.
-        let commit_info = if i + 1 < versions.len() {
+        let count = if i + 1 < versions.len() {
             let prev = &versions[i + 1];
             let from_oid = gitutils::tag_commit_oid(&repo, &prev.tag).ok();
             let to_oid = gitutils::tag_commit_oid(&repo, &version.tag).ok();
-            let count = match (from_oid, to_oid) {
+            Some(match (from_oid, to_oid) {
                 (Some(from), Some(to)) => {
-                    gitutils::count_commits_between(&repo, from, to).unwrap_or(0)
+                    gitutils::count_commits_between(&repo, from, to, &args.path).unwrap_or(0)
                 }
                 _ => 0,
-            };
-            format!("{} commit{}", count, if count == 1 { "" } else { "s" })
+            })
         } else {
             // Oldest release: no prior tag boundary exists, so showing a raw count would
             // include the entire project history and be misleading.
-            "\u{2014}".to_string()
+            None
         };
 
-        rows.push((version.tag.clone(), date_str, commit_info));
+        rows.push((version, time, count));
+    }
+
+    if global.format == OutputFormat::Json {
+        let releases: Vec<_> = rows
+            .iter()
+            .map(|(version, time, count)| {
+                let commit = version_source.commit_id(&repo, &version.tag).ok();
+                let mut value = output::version(version, &source, commit);
+                value["date"] = json!(time.map(format_date));
+                value["timestamp"] = json!(time);
+                value["commits"] = json!(count);
+                value
+            })
+            .collect();
+        output::print(&json!(releases));
+        return Ok(());
     }
 
+    let rows: Vec<(&str, String, String)> = rows
+        .iter()
+        .map(|(version, time, count)| {
+            let date = time.map_or_else(|| "unknown".to_string(), format_date);
+            let commits = match count {
+                Some(count) => format!("{} commit{}", count, if *count == 1 { "" } else { "s" }),
+                None => "\u{2014}".to_string(),
+            };
+            (version.tag.as_str(), date, commits)
+        })
+        .collect();
+
     // Align columns.
     let tag_width = rows.iter().map(|(t, _, _)| t.len()).max().unwrap_or(0);
     let date_width = rows.iter().map(|(_, d, _)| d.len()).max().unwrap_or(0);
@@ -173,45 +947,164 @@ pub fn log_versions(path: &Path, args: &LogArgs) -> Result<(), FlophaError> {
     Ok(())
 }
 
-const SEP: &str = "─";
-
-/// Formats a Unix timestamp as `YYYY-MM-DD`.
-fn format_date(ts: i64) -> String {
-    // Days since Unix epoch.
-    let secs = ts.max(0) as u64;
-    let days_since_epoch = secs / 86400;
-
-    // Gregorian calendar calculation (no external dep needed for dates after 1970).
-    let mut remaining = days_since_epoch;
-    let mut year = 1970u32;
-    loop {
-        let days_in_year = if is_leap(year) { 366 } else { 365 };
-        if remaining < days_in_year {
-            break;
+/// Renders the commits of every release as Markdown, newest first, with the commits
+/// made since the last version under "Unreleased".
+///
+/// The changelog is printed, or prepended to `--output` when given.
+pub fn changelog(
+    path: &Path,
+    global: &GlobalArgs,
+    args: &ChangelogArgs,
+) -> Result<String, FlophaError> {
+    let repo = open_repo(path, global)?;
+    let args = &load_config(&repo, global)?.changelog_args(args);
+    let pattern = args.pattern.clone().unwrap_or(DEFAULT_PATTERN.to_string());
+    let rules = build_rules(&args.rule)?;
+
+    let versions =
+        Versioner::new(TagVersionSource::default().fetch_all(&repo), pattern).all_versions();
+    let first = match &args.since {
+        Some(since) => {
+            versions
+                .iter()
+                .position(|v| &v.tag == since)
+                .ok_or_else(|| FlophaError::VersionNotFound(since.clone()))?
+                + 1
         }
-        remaining -= days_in_year;
-        year += 1;
-    }
-    let leap = is_leap(year);
-    let month_days: &[u64] = if leap {
-        &[31, 29, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31]
-    } else {
-        &[31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31]
+        None => 0,
     };
-    let mut month = 1u32;
-    for &md in month_days {
-        if remaining < md {
-            break;
+    let oids = versions
+        .iter()
+        .map(|v| gitutils::tag_commit_oid(&repo, &v.tag))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut releases = Vec::new();
+    let head = repo.head()?.peel_to_commit()?.id();
+    let unreleased = changelog_entries(&repo, oids.last().copied(), head, &args.path, &rules)?;
+    if args.unreleased || !unreleased.is_empty() {
+        releases.push(Release {
+            title: changelog::UNRELEASED.to_string(),
+            date: None,
+            entries: unreleased,
+        });
+    }
+    if !args.unreleased {
+        for i in (first..versions.len()).rev() {
+            let from = i.checked_sub(1).map(|prev| oids[prev]);
+            releases.push(Release {
+                title: versions[i].tag.clone(),
+                date: Some(format_date(gitutils::tag_time(&repo, &versions[i].tag)?)),
+                entries: changelog_entries(&repo, from, oids[i], &args.path, &rules)?,
+            });
+        }
+    }
+
+    let markdown = changelog::render(&releases);
+    match &args.output {
+        Some(output) => {
+            let file = path.join(output);
+            let io_error = |source| FlophaError::Io {
+                path: file.display().to_string(),
+                source,
+            };
+            let existing = match std::fs::read_to_string(&file) {
+                Ok(content) => content,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+                Err(e) => return Err(io_error(e)),
+            };
+            std::fs::write(&file, changelog::prepend(&existing, &releases)).map_err(io_error)?;
+            log::debug!("Prepended changelog to '{}'", file.display());
         }
-        remaining -= md;
-        month += 1;
+        None if global.format == OutputFormat::Text => print!("{}", markdown),
+        None => {}
+    }
+    if global.format == OutputFormat::Json {
+        output::print(&output::changelog(&releases, &markdown));
+    }
+    Ok(markdown)
+}
+
+fn changelog_entries(
+    repo: &git2::Repository,
+    from: Option<git2::Oid>,
+    to: git2::Oid,
+    paths: &[String],
+    rules: &[BumpRule],
+) -> Result<Vec<Entry>, FlophaError> {
+    let mut entries = Vec::new();
+    for commit in gitutils::commits_between(repo, from, to, paths)? {
+        let hash = commit.as_object().short_id()?;
+        entries.push(Entry::new(
+            commit.message().unwrap_or_default(),
+            hash.as_str().unwrap_or_default(),
+            rules,
+        ));
+    }
+    Ok(entries)
+}
+
+pub fn list_streams(path: &Path, global: &GlobalArgs) -> Result<(), FlophaError> {
+    let repo = open_repo(path, global)?;
+    let config = load_config(&repo, global)?;
+
+    // Resolve each stream's last version once: (name, pattern, source, last version, commit)
+    let rows = config
+        .streams
+        .iter()
+        .map(|(name, stream)| {
+            let source = stream
+                .source
+                .clone()
+                .or(config.source.clone())
+                .unwrap_or_default();
+            let version_source =
+                version_source_factory(&repo, &source, global, TagOptions::default(), None)?;
+            let last = Versioner::new(version_source.fetch_all(&repo), stream.pattern.clone())
+                .last_version();
+            let commit = last
+                .as_ref()
+                .and_then(|version| version_source.commit_id(&repo, &version.tag).ok());
+            Ok((name.as_str(), stream.pattern.as_str(), source, last, commit))
+        })
+        .collect::<Result<Vec<_>, FlophaError>>()?;
+
+    if global.format == OutputFormat::Json {
+        let streams: Vec<_> = rows
+            .iter()
+            .map(|(name, pattern, source, last, commit)| {
+                let last = last
+                    .as_ref()
+                    .map(|version| output::version(version, source, *commit));
+                json!({ "name": name, "pattern": pattern, "last_version": last })
+            })
+            .collect();
+        output::print(&json!(streams));
+        return Ok(());
+    }
+
+    if rows.is_empty() {
+        println!("No streams found");
+        return Ok(());
+    }
+
+    let name_width = rows.iter().map(|row| row.0.len()).max().unwrap_or(0);
+    for (name, _, _, last, _) in &rows {
+        let last = last.as_ref().map_or("\u{2014}", |v| v.tag.as_str());
+        println!(
+            "  {:<name_width$}  {SEP}  {last}",
+            name,
+            name_width = name_width
+        );
     }
-    let day = remaining + 1;
-    format!("{:04}-{:02}-{:02}", year, month, day)
+
+    Ok(())
 }
 
-fn is_leap(year: u32) -> bool {
-    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+const SEP: &str = "─";
+
+/// Formats a Unix timestamp as `YYYY-MM-DD`.
+fn format_date(ts: i64) -> String {
+    CalendarDate::from_timestamp(ts).to_iso()
 }
 
 /// Returns the rule set to use for `--auto`.
@@ -223,52 +1116,66 @@ fn build_rules(raw_rules: &[String]) -> Result<Vec<BumpRule>, FlophaError> {
     if raw_rules.is_empty() {
         return Ok(versioning::conventional_bump_rules());
     }
-    raw_rules.iter().map(|s| parse_bump_rule(s)).collect()
-}
-
-fn parse_bump_rule(s: &str) -> Result<BumpRule, FlophaError> {
-    let (level, pattern) = s.split_once(':').ok_or_else(|| FlophaError::InvalidRule {
-        input: s.to_string(),
-        reason: "expected format '<level>:<pattern>'".to_string(),
-    })?;
-    let increment = match level {
-        "major" => Increment::Major,
-        "minor" => Increment::Minor,
-        "patch" => Increment::Patch,
-        other => {
-            return Err(FlophaError::InvalidRule {
-                input: s.to_string(),
-                reason: format!("unknown level '{}', expected major, minor, or patch", other),
-            })
-        }
-    };
-    BumpRule::new(pattern, increment).map_err(|e| FlophaError::InvalidRule {
-        input: s.to_string(),
-        reason: format!("invalid regex: {}", e),
-    })
+    raw_rules
+        .iter()
+        .map(|s| versioning::parse_bump_rule(s))
+        .collect()
 }
 
-fn version_source_factory(source: &VersionSourceName) -> Box<dyn VersionSource> {
-    match source {
-        VersionSourceName::Branch => Box::new(BranchVersionSource),
-        VersionSourceName::Tag => Box::new(TagVersionSource),
-    }
+fn version_source_factory(
+    repo: &git2::Repository,
+    source: &VersionSourceName,
+    global: &GlobalArgs,
+    tag_options: TagOptions,
+    reachable_from: Option<git2::Oid>,
+) -> Result<Box<dyn VersionSource>, FlophaError> {
+    let remote = global.remote.as_str();
+    Ok(match source {
+        VersionSourceName::Branch => {
+            if tag_options.is_annotated() {
+                log::warn!("--annotate, --message and --sign have no effect with --source branch");
+            }
+            if reachable_from.is_some() {
+                log::warn!("--reachable has no effect with --source branch");
+            }
+            Box::new(BranchVersionSource::new(remote))
+        }
+        VersionSourceName::Tag => {
+            Box::new(TagVersionSource::new(tag_options).reachable_from(reachable_from))
+        }
+        VersionSourceName::Remote => {
+            if reachable_from.is_some() {
+                log::warn!("--reachable has no effect with --source remote");
+            }
+            if global.no_fetch || global.offline {
+                log::debug!("Reading the versions of '{}' from local refs", remote);
+                Box::new(RemoteVersionSource::offline(repo, remote, tag_options)?)
+            } else {
+                Box::new(RemoteVersionSource::list(repo, remote, tag_options)?)
+            }
+        }
+    })
 }
 
-fn versioner_factory(
+/// Resolves `--rev` to a commit, defaulting to HEAD; `None` when HEAD has no commit yet.
+fn target_commit(
     repo: &git2::Repository,
-    pattern: String,
-    source: &VersionSourceName,
-) -> Versioner {
-    let version_source = version_source_factory(source);
-    let versions = version_source.fetch_all(repo);
-    Versioner::new(versions, pattern)
+    rev: Option<&str>,
+) -> Result<Option<git2::Oid>, FlophaError> {
+    match rev {
+        Some(rev) => Ok(Some(repo.revparse_single(rev)?.peel_to_commit()?.id())),
+        None => Ok(repo
+            .head()
+            .and_then(|head| head.peel_to_commit())
+            .ok()
+            .map(|commit| commit.id())),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::cli::LastVersionArgs;
+    use crate::cli::{LastVersionArgs, VersionArgs};
     use crate::versioning::Increment;
     use crate::{gitutils, testutils};
 
@@ -295,11 +1202,11 @@ mod tests {
 
         let args = LastVersionArgs {
             pattern: Some("flopha@{major}.{minor}.{patch}".to_string()),
-            source: VersionSourceName::Tag,
-            checkout: false,
+            source: Some(VersionSourceName::Tag),
+            ..LastVersionArgs::default()
         };
 
-        let result = last_version(td.path(), &args).unwrap();
+        let result = last_version(td.path(), &GlobalArgs::default(), &args).unwrap();
 
         assert_eq!(result, Some("flopha@2.10.11".to_string()));
     }
@@ -316,10 +1223,10 @@ mod tests {
 
         let args = LastVersionArgs {
             pattern: Some("flopha@{major}.{minor}.{patch}".to_string()),
-            source: VersionSourceName::Tag,
-            checkout: false,
+            source: Some(VersionSourceName::Tag),
+            ..LastVersionArgs::default()
         };
-        let result = last_version(td.path(), &args).unwrap();
+        let result = last_version(td.path(), &GlobalArgs::default(), &args).unwrap();
 
         assert_eq!(result, None);
     }
@@ -343,10 +1250,11 @@ mod tests {
 
         let args = LastVersionArgs {
             pattern: Some("flopha@{major}.{minor}.{patch}".to_string()),
-            source: VersionSourceName::Tag,
+            source: Some(VersionSourceName::Tag),
             checkout: true,
+            ..LastVersionArgs::default()
         };
-        last_version(td.path(), &args).unwrap();
+        last_version(td.path(), &GlobalArgs::default(), &args).unwrap();
 
         let tag_id = repo.revparse_single("refs/tags/flopha@1.1.2").unwrap().id();
         let head_id = repo.head().unwrap().peel_to_commit().unwrap().id();
@@ -365,11 +1273,11 @@ mod tests {
 
         let args = LastVersionArgs {
             pattern: Some("release-{major}.{minor}.{patch}".to_string()),
-            source: VersionSourceName::Tag,
-            checkout: false,
+            source: Some(VersionSourceName::Tag),
+            ..LastVersionArgs::default()
         };
 
-        let result = last_version(td.path(), &args).unwrap();
+        let result = last_version(td.path(), &GlobalArgs::default(), &args).unwrap();
 
         assert_eq!(result, None);
     }
@@ -396,11 +1304,11 @@ mod tests {
 
         let args = LastVersionArgs {
             pattern: Some("release/{major}.{minor}.{patch}".to_string()),
-            source: VersionSourceName::Branch,
-            checkout: false,
+            source: Some(VersionSourceName::Branch),
+            ..LastVersionArgs::default()
         };
 
-        let result = last_version(td.path(), &args).unwrap();
+        let result = last_version(td.path(), &GlobalArgs::default(), &args).unwrap();
 
         assert_eq!(result, Some("release/2.10.11".to_string()));
     }
@@ -423,11 +1331,11 @@ mod tests {
 
         let args = LastVersionArgs {
             pattern: Some("release/{major}.{minor}.{patch}".to_string()),
-            source: VersionSourceName::Branch,
-            checkout: false,
+            source: Some(VersionSourceName::Branch),
+            ..LastVersionArgs::default()
         };
 
-        let result = last_version(td.path(), &args).unwrap();
+        let result = last_version(td.path(), &GlobalArgs::default(), &args).unwrap();
 
         assert_eq!(result, Some("release/2.0.0".to_string()));
     }
@@ -449,10 +1357,11 @@ mod tests {
 
         let args = LastVersionArgs {
             pattern: Some("release/{major}.{minor}.{patch}".to_string()),
-            source: VersionSourceName::Branch,
+            source: Some(VersionSourceName::Branch),
             checkout: true,
+            ..LastVersionArgs::default()
         };
-        last_version(td.path(), &args).unwrap();
+        last_version(td.path(), &GlobalArgs::default(), &args).unwrap();
 
         let branch_id = repo
             .revparse_single("refs/heads/release/2.1.0")
@@ -463,7 +1372,291 @@ mod tests {
     }
 
     #[test]
-    fn test_next_version_returns_next_version_with_given_pattern() {
+    fn test_fetch_keeps_diverged_local_branches() {
+        let (td, repo) = testutils::init_repo();
+        let (_remote_td, mut remote) = testutils::init_remote(&repo);
+        create_new_remote_branch(&repo, &mut remote, "release/1.0.0");
+        create_new_remote_branch(&repo, &mut remote, "release/2.0.0");
+
+        // Unpushed work on a release branch, and a branch only the remote still has.
+        gitutils::checkout_branch(&repo, "release/1.0.0", false).unwrap();
+        let local_work = testutils::commit_file(&repo, "fix.txt", "fix", "fix: local work");
+        gitutils::checkout_branch(&repo, "main", false).unwrap();
+        repo.find_branch("release/2.0.0", git2::BranchType::Local)
+            .unwrap()
+            .delete()
+            .unwrap();
+
+        let args = LastVersionArgs {
+            pattern: Some("release/{major}.{minor}.{patch}".to_string()),
+            source: Some(VersionSourceName::Branch),
+            ..LastVersionArgs::default()
+        };
+        let result = last_version(td.path(), &GlobalArgs::default(), &args).unwrap();
+        assert_eq!(result, Some("release/2.0.0".to_string()));
+
+        let local = repo
+            .find_branch("release/1.0.0", git2::BranchType::Local)
+            .unwrap();
+        assert_eq!(local.get().target(), Some(local_work));
+        let tracking = repo
+            .find_reference("refs/remotes/origin/release/1.0.0")
+            .unwrap();
+        assert_ne!(tracking.target(), Some(local_work));
+
+        let names = BranchVersionSource::new("origin").fetch_all(&repo);
+        assert_eq!(names.iter().filter(|n| *n == "release/1.0.0").count(), 1);
+        assert!(names.iter().any(|n| n == "release/2.0.0"));
+    }
+
+    #[test]
+    fn test_last_version_branch_checks_out_remote_only_branch() {
+        let (td, repo) = testutils::init_repo();
+        let (_remote_td, mut remote) = testutils::init_remote(&repo);
+        let (_fork_td, mut fork) = testutils::init_named_remote(&repo, "fork");
+        create_new_remote_branch(&repo, &mut remote, "release/1.0.0");
+        create_new_remote_branch(&repo, &mut remote, "release/1.1.0");
+        create_new_remote_branch(&repo, &mut fork, "release/9.0.0");
+
+        // Like a CI clone, only main exists locally.
+        gitutils::checkout_branch(&repo, "main", false).unwrap();
+        for name in ["release/1.0.0", "release/1.1.0", "release/9.0.0"] {
+            repo.find_branch(name, git2::BranchType::Local)
+                .unwrap()
+                .delete()
+                .unwrap();
+        }
+
+        let args = LastVersionArgs {
+            pattern: Some("release/{major}.{minor}.{patch}".to_string()),
+            source: Some(VersionSourceName::Branch),
+            checkout: true,
+            ..LastVersionArgs::default()
+        };
+        let result = last_version(td.path(), &GlobalArgs::default(), &args).unwrap();
+        assert_eq!(result, Some("release/1.1.0".to_string()));
+
+        let branch = repo
+            .find_branch("release/1.1.0", git2::BranchType::Local)
+            .unwrap();
+        let upstream = branch.upstream().unwrap();
+        assert_eq!(upstream.name().unwrap(), Some("origin/release/1.1.0"));
+        assert_eq!(
+            repo.head().unwrap().name(),
+            Some("refs/heads/release/1.1.0")
+        );
+    }
+
+    #[test]
+    fn test_remote_source_lists_tags_without_fetching() {
+        let (td, repo) = testutils::init_repo();
+        let (_remote_td, mut remote) = testutils::init_remote(&repo);
+        create_new_remote_tag(&repo, &mut remote, "v1.0.0", true);
+        create_new_remote_tag(&repo, &mut remote, "v1.1.0", true);
+        let release = gitutils::commit(&repo, "New commit").unwrap();
+        gitutils::annotated_tag_oid(&repo, release, "v1.2.0", "Release 1.2.0", false).unwrap();
+        remote.push(&["refs/tags/v1.2.0"], None).unwrap();
+        repo.tag_delete("v1.2.0").unwrap();
+        testutils::commit_file(&repo, "app.txt", "1", "feat: remote lookup");
+
+        let args = LastVersionArgs {
+            source: Some(VersionSourceName::Remote),
+            ..LastVersionArgs::default()
+        };
+        let result = last_version(td.path(), &GlobalArgs::default(), &args).unwrap();
+        assert_eq!(result, Some("v1.2.0".to_string()));
+        assert!(repo.find_reference("refs/tags/v1.2.0").is_err());
+
+        let args = NextVersionArgs {
+            version: VersionArgs {
+                auto: true,
+                source: Some(VersionSourceName::Remote),
+                ..VersionArgs::default()
+            },
+            ..NextVersionArgs::default()
+        };
+        let result = next_version(td.path(), &GlobalArgs::default(), &args).unwrap();
+        assert_eq!(result, Some("v1.3.0".to_string()));
+        // --auto fetched the last version's tag, and nothing else.
+        assert!(repo.find_reference("refs/tags/v1.2.0").is_ok());
+        assert!(repo.find_reference("refs/tags/v1.1.0").is_err());
+    }
+
+    #[test]
+    fn test_remote_source_reports_unreachable_remote() {
+        let (td, repo) = testutils::init_repo();
+        let missing = td.path().join("missing.git");
+        repo.remote("origin", missing.to_str().unwrap()).unwrap();
+        testutils::commit_file(&repo, "app.txt", "1", "feat: first");
+
+        let args = NextVersionArgs {
+            version: VersionArgs {
+                initial: Some("0.0.0".to_string()),
+                source: Some(VersionSourceName::Remote),
+                ..VersionArgs::default()
+            },
+            ..NextVersionArgs::default()
+        };
+        let result = next_version(td.path(), &GlobalArgs::default(), &args);
+        assert!(matches!(result, Err(FlophaError::Git(_))));
+    }
+
+    #[test]
+    fn test_remote_source_reads_local_refs_offline() {
+        let (td, repo) = testutils::init_repo();
+        let (_remote_td, mut remote) = testutils::init_remote(&repo);
+        create_new_remote_tag(&repo, &mut remote, "v1.0.0", false);
+        create_new_remote_branch(&repo, &mut remote, "v1.1.0");
+        gitutils::fetch_all(&mut remote).unwrap();
+        gitutils::checkout_branch(&repo, "main", false).unwrap();
+        repo.find_branch("v1.1.0", git2::BranchType::Local)
+            .unwrap()
+            .delete()
+            .unwrap();
+        // Pushed by someone else: offline, a version only on the remote is not seen.
+        testutils::commit_file(&repo, "app.txt", "1", "feat: elsewhere");
+        repo.remote_anonymous(remote.url().unwrap())
+            .unwrap()
+            .push(&["refs/heads/main:refs/heads/v2.0.0"], None)
+            .unwrap();
+        let global = GlobalArgs {
+            offline: true,
+            ..GlobalArgs::default()
+        };
+
+        let args = LastVersionArgs {
+            source: Some(VersionSourceName::Remote),
+            ..LastVersionArgs::default()
+        };
+        let result = last_version(td.path(), &global, &args).unwrap();
+        assert_eq!(result, Some("v1.1.0".to_string()));
+        let result = last_version(td.path(), &GlobalArgs::default(), &args).unwrap();
+        assert_eq!(result, Some("v2.0.0".to_string()));
+    }
+
+    #[test]
+    fn test_auto_fails_when_shallow_clone_misses_last_version() {
+        let (td, repo) = testutils::init_repo();
+        let global = GlobalArgs {
+            no_fetch: true,
+            ..GlobalArgs::default()
+        };
+        let released = testutils::commit_file(&repo, "app.txt", "1", "feat: first");
+        gitutils::tag_oid(&repo, released, "v1.0.0").unwrap();
+        testutils::commit_file(&repo, "app.txt", "2", "feat!: breaking");
+        let boundary = testutils::commit_file(&repo, "app.txt", "3", "fix: third");
+        // Like `git clone --depth 1`: history stops at the boundary commit.
+        std::fs::write(repo.path().join("shallow"), format!("{}\n", boundary)).unwrap();
+        assert!(repo.is_shallow());
+
+        let args = NextVersionArgs {
+            version: VersionArgs {
+                auto: true,
+                ..VersionArgs::default()
+            },
+            ..NextVersionArgs::default()
+        };
+        let err = next_version(td.path(), &global, &args).unwrap_err();
+        assert!(
+            matches!(err, FlophaError::ShallowHistory { tag: Some(ref tag) } if tag == "v1.0.0")
+        );
+
+        // --offline never tries to deepen, even without a remote to reach.
+        let offline = GlobalArgs {
+            offline: true,
+            ..GlobalArgs::default()
+        };
+        let err = next_version(td.path(), &offline, &args).unwrap_err();
+        assert!(matches!(err, FlophaError::ShallowHistory { .. }));
+
+        std::fs::remove_file(repo.path().join("shallow")).unwrap();
+        let result = next_version(td.path(), &global, &args).unwrap();
+        assert_eq!(result, Some("v2.0.0".to_string()));
+    }
+
+    #[test]
+    fn test_auto_reads_shallow_clone_holding_the_commits_since_last_version() {
+        let (td, repo) = testutils::init_repo();
+        let global = GlobalArgs {
+            no_fetch: true,
+            ..GlobalArgs::default()
+        };
+        let boundary = testutils::commit_file(&repo, "app.txt", "1", "feat: first");
+        // v1.0.0 was released from another line of history.
+        gitutils::checkout_branch(&repo, "maintenance", true).unwrap();
+        let released = testutils::commit_file(&repo, "app.txt", "2", "fix: backport");
+        gitutils::tag_oid(&repo, released, "v1.0.0").unwrap();
+        gitutils::checkout_branch(&repo, "main", false).unwrap();
+        testutils::commit_file(&repo, "app.txt", "3", "fix: third");
+        std::fs::write(repo.path().join("shallow"), format!("{}\n", boundary)).unwrap();
+
+        let args = NextVersionArgs {
+            version: VersionArgs {
+                auto: true,
+                ..VersionArgs::default()
+            },
+            ..NextVersionArgs::default()
+        };
+        let result = next_version(td.path(), &global, &args).unwrap();
+        assert_eq!(result, Some("v1.0.1".to_string()));
+
+        // From --initial every commit counts, and the boundary's parents are missing.
+        let args = NextVersionArgs {
+            version: VersionArgs {
+                auto: true,
+                initial: Some("0.0.0".to_string()),
+                pattern: Some("app@{major}.{minor}.{patch}".to_string()),
+                ..VersionArgs::default()
+            },
+            ..NextVersionArgs::default()
+        };
+        let err = next_version(td.path(), &global, &args).unwrap_err();
+        assert!(matches!(err, FlophaError::ShallowHistory { tag: None }));
+    }
+
+    #[test]
+    fn test_current_version_describes_commits_since_reachable_release() {
+        let (td, repo) = testutils::init_repo();
+        let global = GlobalArgs {
+            no_fetch: true,
+            ..GlobalArgs::default()
+        };
+        let release = testutils::commit_file(&repo, "app.txt", "1", "feat: app");
+        gitutils::tag_oid(&repo, release, "v1.4.0").unwrap();
+        let args = CurrentVersionArgs::default();
+        assert_eq!(
+            current_version(td.path(), &global, &args).unwrap(),
+            Some("v1.4.0".to_string())
+        );
+
+        // A release on another line of history is not reachable from HEAD.
+        let unrelated = testutils::commit_file(&repo, "app.txt", "2", "feat: other line");
+        gitutils::tag_oid(&repo, unrelated, "v9.0.0").unwrap();
+        let release_commit = repo.find_object(release, None).unwrap();
+        repo.reset(&release_commit, git2::ResetType::Hard, None)
+            .unwrap();
+        testutils::commit_file(&repo, "app.txt", "3", "fix: one");
+        let head = testutils::commit_file(&repo, "app.txt", "4", "fix: two");
+        let sha = repo.find_object(head, None).unwrap().short_id().unwrap();
+        let sha = sha.as_str().unwrap();
+        assert_eq!(
+            current_version(td.path(), &global, &args).unwrap(),
+            Some(format!("v1.4.1-dev.2+g{}", sha))
+        );
+
+        std::fs::write(td.path().join("app.txt"), "5").unwrap();
+        let args = CurrentVersionArgs {
+            template: Some("{version}+{distance}.{branch}{dirty}".to_string()),
+            ..CurrentVersionArgs::default()
+        };
+        assert_eq!(
+            current_version(td.path(), &global, &args).unwrap(),
+            Some("v1.4.0+2.main.dirty".to_string())
+        );
+    }
+
+    #[test]
+    fn test_next_version_returns_next_version_with_given_pattern() {
         let (td, repo) = testutils::init_repo();
         let (_remote_td, mut remote) = testutils::init_remote(&repo);
         let tags = vec![
@@ -485,15 +1678,15 @@ mod tests {
         gitutils::commit(&repo, "New commit").unwrap();
 
         let args = NextVersionArgs {
-            pattern: Some("flopha@{major}.{minor}.{patch}".to_string()),
-            increment: Increment::Patch,
-            auto: false,
-            rule: vec![],
-            pre: None,
-            source: VersionSourceName::Tag,
-            create: false,
+            version: VersionArgs {
+                pattern: Some("flopha@{major}.{minor}.{patch}".to_string()),
+                increment: Some(Increment::Patch),
+                source: Some(VersionSourceName::Tag),
+                ..VersionArgs::default()
+            },
+            ..NextVersionArgs::default()
         };
-        let result = next_version(td.path(), &args).unwrap();
+        let result = next_version(td.path(), &GlobalArgs::default(), &args).unwrap();
 
         assert_eq!(result, Some("flopha@2.10.12".to_string()))
     }
@@ -517,21 +1710,260 @@ mod tests {
         gitutils::commit(&repo, "New commit").unwrap();
 
         let args = NextVersionArgs {
-            pattern: Some("flopha@{major}.{minor}.{patch}".to_string()),
-            increment: Increment::Patch,
-            auto: false,
-            rule: vec![],
-            pre: None,
-            source: VersionSourceName::Tag,
+            version: VersionArgs {
+                pattern: Some("flopha@{major}.{minor}.{patch}".to_string()),
+                increment: Some(Increment::Patch),
+                source: Some(VersionSourceName::Tag),
+                ..VersionArgs::default()
+            },
             create: true,
+            ..NextVersionArgs::default()
         };
-        next_version(td.path(), &args).unwrap();
+        next_version(td.path(), &GlobalArgs::default(), &args).unwrap();
 
         let tag_id = repo.revparse_single("refs/tags/flopha@1.1.3").unwrap().id();
         let head_id = repo.head().unwrap().peel_to_commit().unwrap().id();
         assert_eq!(tag_id, head_id);
     }
 
+    #[test]
+    fn test_next_version_creates_annotated_tag_with_message() {
+        let (td, repo) = testutils::init_repo();
+        let (_remote_td, mut remote) = testutils::init_remote(&repo);
+        create_new_remote_tag(&repo, &mut remote, "v1.0.0", false);
+        gitutils::commit(&repo, "New commit").unwrap();
+
+        let args = NextVersionArgs {
+            version: VersionArgs {
+                increment: Some(Increment::Minor),
+                ..VersionArgs::default()
+            },
+            create: true,
+            message: Some("Release {version}".to_string()),
+            ..NextVersionArgs::default()
+        };
+        next_version(td.path(), &GlobalArgs::default(), &args).unwrap();
+
+        let tag = repo
+            .revparse_single("refs/tags/v1.1.0")
+            .unwrap()
+            .peel_to_tag()
+            .unwrap();
+        let head_id = repo.head().unwrap().peel_to_commit().unwrap().id();
+        assert_eq!(tag.target_id(), head_id);
+        assert_eq!(tag.message(), Some("Release v1.1.0\n"));
+        assert_eq!(tag.tagger().unwrap().name(), Some("name"));
+    }
+
+    #[test]
+    fn test_next_version_writes_version_into_files() {
+        let (td, repo) = testutils::init_repo();
+        let (_remote_td, mut remote) = testutils::init_remote(&repo);
+        create_new_remote_tag(&repo, &mut remote, "v1.0.0", false);
+        std::fs::write(
+            td.path().join("Cargo.toml"),
+            "[package]\nname = \"app\"\nversion = \"1.0.0\"\n",
+        )
+        .unwrap();
+        std::fs::write(td.path().join("VERSION"), "1.0.0\n").unwrap();
+
+        let args = NextVersionArgs {
+            version: VersionArgs {
+                increment: Some(Increment::Minor),
+                ..VersionArgs::default()
+            },
+            write_files: true,
+            file: vec!["Cargo.toml".to_string(), "VERSION".to_string()],
+            ..NextVersionArgs::default()
+        };
+        let result = next_version(td.path(), &GlobalArgs::default(), &args).unwrap();
+
+        assert_eq!(result, Some("v1.1.0".to_string()));
+        assert_eq!(
+            std::fs::read_to_string(td.path().join("Cargo.toml")).unwrap(),
+            "[package]\nname = \"app\"\nversion = \"1.1.0\"\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(td.path().join("VERSION")).unwrap(),
+            "1.1.0\n"
+        );
+    }
+
+    fn release_args() -> ReleaseArgs {
+        ReleaseArgs {
+            version: VersionArgs {
+                increment: Some(Increment::Minor),
+                ..VersionArgs::default()
+            },
+            file: vec!["Cargo.toml".to_string()],
+            push: true,
+            ..ReleaseArgs::default()
+        }
+    }
+
+    #[test]
+    fn test_release_tags_release_commit_and_pushes_both() {
+        let (td, repo) = testutils::init_repo();
+        let (remote_td, mut remote) = testutils::init_remote(&repo);
+        testutils::commit_file(
+            &repo,
+            "Cargo.toml",
+            "[package]\nversion = \"1.0.0\"\n",
+            "init",
+        );
+        create_new_remote_tag(&repo, &mut remote, "v1.0.0", false);
+
+        let result = release(td.path(), &GlobalArgs::default(), &release_args()).unwrap();
+
+        assert_eq!(result, Some("v1.1.0".to_string()));
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.message(), Some("chore(release): v1.1.0"));
+        assert_eq!(
+            gitutils::tag_commit_oid(&repo, "v1.1.0").unwrap(),
+            head.id()
+        );
+        let blob = head
+            .tree()
+            .unwrap()
+            .get_path(Path::new("Cargo.toml"))
+            .unwrap()
+            .to_object(&repo)
+            .unwrap()
+            .peel_to_blob()
+            .unwrap();
+        assert_eq!(blob.content(), b"[package]\nversion = \"1.1.0\"\n");
+        assert!(repo.statuses(None).unwrap().is_empty());
+
+        let remote_repo = git2::Repository::open(remote_td.path()).unwrap();
+        let remote_main = remote_repo.find_reference("refs/heads/main").unwrap();
+        assert_eq!(remote_main.target(), Some(head.id()));
+        assert!(remote_repo.find_reference("refs/tags/v1.1.0").is_ok());
+    }
+
+    #[test]
+    fn test_release_explains_auto_bump() {
+        let (td, repo) = testutils::init_repo();
+        let (_remote_td, mut remote) = testutils::init_remote(&repo);
+        testutils::commit_file(&repo, "VERSION", "1.0.0\n", "init");
+        create_new_remote_tag(&repo, &mut remote, "v1.0.0", false);
+        testutils::commit_file(&repo, "app.txt", "1", "feat: add export");
+
+        let args = ReleaseArgs {
+            version: VersionArgs {
+                auto: true,
+                explain: true,
+                ..VersionArgs::default()
+            },
+            file: vec!["VERSION".to_string()],
+            ..ReleaseArgs::default()
+        };
+        let (tag, explanation) = run_release(td.path(), &GlobalArgs::default(), &args).unwrap();
+
+        assert_eq!(tag, Some("v1.1.0".to_string()));
+        let explanation = explanation.unwrap();
+        assert_eq!(explanation.report.increment, Increment::Minor);
+        assert_eq!(explanation.shas.len(), 1);
+        assert_eq!(explanation.report.commits[0].subject, "feat: add export");
+        assert!(gitutils::tag_commit_oid(&repo, "v1.1.0").is_ok());
+    }
+
+    #[test]
+    fn test_release_rolls_back_when_a_step_fails() {
+        let (td, repo) = testutils::init_repo();
+        let (_remote_td, mut remote) = testutils::init_remote(&repo);
+        testutils::commit_file(
+            &repo,
+            "Cargo.toml",
+            "[package]\nversion = \"1.0.0\"\n",
+            "init",
+        );
+        create_new_remote_tag(&repo, &mut remote, "v1.0.0", false);
+        let prior_head = repo.head().unwrap().peel_to_commit().unwrap().id();
+
+        // The fetch is skipped, so the push is the first step to notice the missing remote.
+        let global = GlobalArgs {
+            remote: "missing".to_string(),
+            no_fetch: true,
+            ..GlobalArgs::default()
+        };
+        let result = release(td.path(), &global, &release_args());
+
+        assert!(matches!(result, Err(FlophaError::RemoteNotFound { .. })));
+        assert_eq!(
+            repo.head().unwrap().peel_to_commit().unwrap().id(),
+            prior_head
+        );
+        assert_eq!(repo.head().unwrap().name(), Some("refs/heads/main"));
+        assert!(repo.find_reference("refs/tags/v1.1.0").is_err());
+        assert_eq!(
+            std::fs::read_to_string(td.path().join("Cargo.toml")).unwrap(),
+            "[package]\nversion = \"1.0.0\"\n"
+        );
+        assert!(repo.statuses(None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_release_refuses_empty_commit() {
+        let (td, repo) = testutils::init_repo();
+        let (_remote_td, mut remote) = testutils::init_remote(&repo);
+        create_new_remote_tag(&repo, &mut remote, "v1.0.0", false);
+        let prior_head = repo.head().unwrap().peel_to_commit().unwrap().id();
+
+        let args = ReleaseArgs {
+            file: Vec::new(),
+            push: false,
+            ..release_args()
+        };
+        let result = release(td.path(), &GlobalArgs::default(), &args);
+
+        assert!(matches!(result, Err(FlophaError::EmptyRelease(ref tag)) if tag == "v1.1.0"));
+        assert_eq!(
+            repo.head().unwrap().peel_to_commit().unwrap().id(),
+            prior_head
+        );
+        assert!(repo.find_reference("refs/tags/v1.1.0").is_err());
+    }
+
+    #[test]
+    #[ignore = "requires ssh-keygen"]
+    fn test_next_version_signs_tag_with_ssh_key() {
+        let (td, repo) = testutils::init_repo();
+        let (_remote_td, mut remote) = testutils::init_remote(&repo);
+        create_new_remote_tag(&repo, &mut remote, "v1.0.0", false);
+        gitutils::commit(&repo, "New commit").unwrap();
+
+        let key_td = tempfile::TempDir::new().unwrap();
+        let key = key_td.path().join("id_ed25519");
+        let status = std::process::Command::new("ssh-keygen")
+            .args(["-q", "-t", "ed25519", "-N", "", "-f"])
+            .arg(&key)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let mut config = repo.config().unwrap();
+        config.set_str("gpg.format", "ssh").unwrap();
+        config
+            .set_str("user.signingkey", key.to_str().unwrap())
+            .unwrap();
+
+        let args = NextVersionArgs {
+            version: VersionArgs {
+                increment: Some(Increment::Patch),
+                ..VersionArgs::default()
+            },
+            create: true,
+            sign: true,
+            ..NextVersionArgs::default()
+        };
+        next_version(td.path(), &GlobalArgs::default(), &args).unwrap();
+
+        let tag_id = repo.revparse_single("refs/tags/v1.0.1").unwrap().id();
+        let raw = repo.odb().unwrap().read(tag_id).unwrap().data().to_vec();
+        let raw = String::from_utf8(raw).unwrap();
+        assert!(raw.contains("tag v1.0.1\n"));
+        assert!(raw.contains("\n\nv1.0.1\n-----BEGIN SSH SIGNATURE-----\n"));
+    }
+
     #[test]
     fn next_version_branch_returns_next_version_with_pattern() {
         let (td, repo) = testutils::init_repo();
@@ -554,15 +1986,15 @@ mod tests {
         gitutils::commit(&repo, "New commit").unwrap();
 
         let args = NextVersionArgs {
-            pattern: Some("release/{major}.{minor}.{patch}".to_string()),
-            increment: Increment::Patch,
-            auto: false,
-            rule: vec![],
-            pre: None,
-            source: VersionSourceName::Branch,
-            create: false,
+            version: VersionArgs {
+                pattern: Some("release/{major}.{minor}.{patch}".to_string()),
+                increment: Some(Increment::Patch),
+                source: Some(VersionSourceName::Branch),
+                ..VersionArgs::default()
+            },
+            ..NextVersionArgs::default()
         };
-        let result = next_version(td.path(), &args).unwrap();
+        let result = next_version(td.path(), &GlobalArgs::default(), &args).unwrap();
 
         assert_eq!(result, Some("release/2.10.12".to_string()))
     }
@@ -578,16 +2010,16 @@ mod tests {
         }
 
         let args = NextVersionArgs {
-            pattern: Some("release/{major}.{minor}.{patch}".to_string()),
-            increment: Increment::Patch,
-            auto: false,
-            rule: vec![],
-            pre: None,
-            source: VersionSourceName::Branch,
-            create: false,
+            version: VersionArgs {
+                pattern: Some("release/{major}.{minor}.{patch}".to_string()),
+                increment: Some(Increment::Patch),
+                source: Some(VersionSourceName::Branch),
+                ..VersionArgs::default()
+            },
+            ..NextVersionArgs::default()
         };
 
-        let result = next_version(td.path(), &args).unwrap();
+        let result = next_version(td.path(), &GlobalArgs::default(), &args).unwrap();
 
         assert_eq!(result, None);
     }
@@ -605,15 +2037,16 @@ mod tests {
         gitutils::commit(&repo, "New commit").unwrap();
 
         let args = NextVersionArgs {
-            pattern: Some("release/{major}.{minor}.{patch}".to_string()),
-            increment: Increment::Minor,
-            auto: false,
-            rule: vec![],
-            pre: None,
-            source: VersionSourceName::Branch,
+            version: VersionArgs {
+                pattern: Some("release/{major}.{minor}.{patch}".to_string()),
+                increment: Some(Increment::Minor),
+                source: Some(VersionSourceName::Branch),
+                ..VersionArgs::default()
+            },
             create: true,
+            ..NextVersionArgs::default()
         };
-        let result = next_version(td.path(), &args).unwrap();
+        let result = next_version(td.path(), &GlobalArgs::default(), &args).unwrap();
 
         assert_eq!(result, Some("release/2.1.0".to_string()));
 
@@ -624,6 +2057,185 @@ mod tests {
         }));
     }
 
+    #[test]
+    fn test_next_version_branch_auto_reads_commits_since_branch() {
+        let (td, repo) = testutils::init_repo();
+        let global = GlobalArgs {
+            no_fetch: true,
+            ..GlobalArgs::default()
+        };
+        let released = testutils::commit_file(&repo, "app.txt", "1", "fix: one");
+        repo.branch("release/1.0.0", &repo.find_commit(released).unwrap(), false)
+            .unwrap();
+        testutils::commit_file(&repo, "app.txt", "2", "feat: two");
+
+        let args = NextVersionArgs {
+            version: VersionArgs {
+                pattern: Some("release/{major}.{minor}.{patch}".to_string()),
+                auto: true,
+                source: Some(VersionSourceName::Branch),
+                ..VersionArgs::default()
+            },
+            ..NextVersionArgs::default()
+        };
+        let result = next_version(td.path(), &global, &args).unwrap();
+
+        assert_eq!(result, Some("release/1.1.0".to_string()));
+    }
+
+    #[test]
+    fn test_branch_create_keeps_existing_branch() {
+        let (_td, repo) = testutils::init_repo();
+        let existing = gitutils::commit(&repo, "First commit").unwrap();
+        gitutils::checkout_branch(&repo, "release/1.1.0", true).unwrap();
+        gitutils::checkout_branch(&repo, "main", false).unwrap();
+        let head = gitutils::commit(&repo, "New commit").unwrap();
+
+        let result = BranchVersionSource::new("origin").create(&repo, "release/1.1.0", head);
+
+        assert!(matches!(result, Err(FlophaError::BranchExists(ref b)) if b == "release/1.1.0"));
+        let branch = repo
+            .find_branch("release/1.1.0", git2::BranchType::Local)
+            .unwrap();
+        assert_eq!(branch.get().target(), Some(existing));
+    }
+
+    #[test]
+    fn test_next_version_reachable_stays_on_maintenance_line() {
+        let (td, repo) = testutils::init_repo();
+        let global = GlobalArgs {
+            no_fetch: true,
+            ..GlobalArgs::default()
+        };
+        let maintenance = testutils::commit_file(&repo, "app.txt", "1", "fix: one");
+        gitutils::tag_oid(&repo, maintenance, "v1.8.4").unwrap();
+        let main = testutils::commit_file(&repo, "app.txt", "2", "feat!: two");
+        gitutils::tag_oid(&repo, main, "v3.0.0").unwrap();
+
+        repo.branch(
+            "release/1.x",
+            &repo.find_commit(maintenance).unwrap(),
+            false,
+        )
+        .unwrap();
+        gitutils::checkout_branch(&repo, "release/1.x", false).unwrap();
+        testutils::commit_file(&repo, "app.txt", "3", "feat: backport");
+
+        let mut args = NextVersionArgs {
+            version: VersionArgs {
+                auto: true,
+                ..VersionArgs::default()
+            },
+            ..NextVersionArgs::default()
+        };
+        // Without --reachable the backported feature bumps the 3.x line.
+        let result = next_version(td.path(), &global, &args).unwrap();
+        assert_eq!(result, Some("v3.1.0".to_string()));
+
+        args.version.reachable = true;
+        let result = next_version(td.path(), &global, &args).unwrap();
+        assert_eq!(result, Some("v1.9.0".to_string()));
+
+        let args = LastVersionArgs {
+            reachable: true,
+            ..LastVersionArgs::default()
+        };
+        let result = last_version(td.path(), &global, &args).unwrap();
+        assert_eq!(result, Some("v1.8.4".to_string()));
+    }
+
+    #[test]
+    fn test_next_version_at_rev_tags_that_commit() {
+        let (td, repo) = testutils::init_repo();
+        let global = GlobalArgs {
+            no_fetch: true,
+            ..GlobalArgs::default()
+        };
+        let released = testutils::commit_file(&repo, "app.txt", "1", "fix: one");
+        gitutils::tag_oid(&repo, released, "v1.0.0").unwrap();
+        let tested = testutils::commit_file(&repo, "app.txt", "2", "feat: two");
+        let head = testutils::commit_file(&repo, "app.txt", "3", "feat!: three");
+
+        let args = NextVersionArgs {
+            version: VersionArgs {
+                auto: true,
+                ..VersionArgs::default()
+            },
+            rev: Some(tested.to_string()),
+            create: true,
+            ..NextVersionArgs::default()
+        };
+        let result = next_version(td.path(), &global, &args).unwrap();
+        assert_eq!(result, Some("v1.1.0".to_string()));
+        assert_eq!(gitutils::tag_commit_oid(&repo, "v1.1.0").unwrap(), tested);
+        assert_eq!(repo.head().unwrap().peel_to_commit().unwrap().id(), head);
+
+        let mut args = LastVersionArgs {
+            rev: Some("HEAD~2".to_string()),
+            ..LastVersionArgs::default()
+        };
+        let result = last_version(td.path(), &global, &args).unwrap();
+        assert_eq!(result, Some("v1.0.0".to_string()));
+        args.rev = Some("main".to_string());
+        let result = last_version(td.path(), &global, &args).unwrap();
+        assert_eq!(result, Some("v1.1.0".to_string()));
+    }
+
+    #[test]
+    fn test_next_version_create_refuses_to_move_existing_tag() {
+        let (td, repo) = testutils::init_repo();
+        let global = GlobalArgs {
+            no_fetch: true,
+            ..GlobalArgs::default()
+        };
+        let released = testutils::commit_file(&repo, "app.txt", "1", "fix: one");
+        gitutils::tag_oid(&repo, released, "v1.0.0").unwrap();
+        testutils::commit_file(&repo, "app.txt", "2", "feat: two");
+        let head = testutils::commit_file(&repo, "app.txt", "3", "feat: three");
+        gitutils::tag_oid(&repo, head, "v1.1.0").unwrap();
+
+        // Only v1.0.0 is reachable from HEAD~1, so it computes the existing v1.1.0.
+        let args = NextVersionArgs {
+            version: VersionArgs {
+                auto: true,
+                reachable: true,
+                ..VersionArgs::default()
+            },
+            rev: Some("HEAD~1".to_string()),
+            create: true,
+            ..NextVersionArgs::default()
+        };
+        let err = next_version(td.path(), &global, &args).unwrap_err();
+        assert!(matches!(err, FlophaError::TagExists(ref tag) if tag == "v1.1.0"));
+        assert_eq!(gitutils::tag_commit_oid(&repo, "v1.1.0").unwrap(), head);
+        assert!(gitutils::tag_oid(&repo, released, "v1.1.0").is_err());
+    }
+
+    #[test]
+    fn test_next_version_auto_from_initial_reads_whole_history() {
+        let (td, repo) = testutils::init_repo();
+        let global = GlobalArgs {
+            no_fetch: true,
+            ..GlobalArgs::default()
+        };
+        testutils::commit_file(&repo, "app.txt", "1", "feat: first feature");
+        testutils::commit_file(&repo, "app.txt", "2", "fix: first fix");
+
+        let mut args = NextVersionArgs {
+            version: VersionArgs {
+                auto: true,
+                pre: Some("rc".to_string()),
+                ..VersionArgs::default()
+            },
+            ..NextVersionArgs::default()
+        };
+        assert_eq!(next_version(td.path(), &global, &args).unwrap(), None);
+
+        args.version.initial = Some("0.0.0".to_string());
+        let result = next_version(td.path(), &global, &args).unwrap();
+        assert_eq!(result, Some("v0.1.0-rc.1".to_string()));
+    }
+
     #[test]
     fn test_next_version_auto_detects_feat_as_minor() {
         let (td, repo) = testutils::init_repo();
@@ -637,15 +2249,16 @@ mod tests {
         gitutils::commit(&repo, "feat: add new command").unwrap();
 
         let args = NextVersionArgs {
-            pattern: Some("v{major}.{minor}.{patch}".to_string()),
-            increment: Increment::Patch,
-            auto: true,
-            rule: vec![],
-            pre: None,
-            source: VersionSourceName::Tag,
-            create: false,
+            version: VersionArgs {
+                pattern: Some("v{major}.{minor}.{patch}".to_string()),
+                increment: Some(Increment::Patch),
+                auto: true,
+                source: Some(VersionSourceName::Tag),
+                ..VersionArgs::default()
+            },
+            ..NextVersionArgs::default()
         };
-        let result = next_version(td.path(), &args).unwrap();
+        let result = next_version(td.path(), &GlobalArgs::default(), &args).unwrap();
 
         assert_eq!(result, Some("v1.2.0".to_string()));
     }
@@ -663,15 +2276,16 @@ mod tests {
         gitutils::commit(&repo, "fix: something").unwrap();
 
         let args = NextVersionArgs {
-            pattern: Some("v{major}.{minor}.{patch}".to_string()),
-            increment: Increment::Patch,
-            auto: false,
-            rule: vec![],
-            pre: Some("alpha".to_string()),
-            source: VersionSourceName::Tag,
-            create: false,
+            version: VersionArgs {
+                pattern: Some("v{major}.{minor}.{patch}".to_string()),
+                increment: Some(Increment::Patch),
+                pre: Some("alpha".to_string()),
+                source: Some(VersionSourceName::Tag),
+                ..VersionArgs::default()
+            },
+            ..NextVersionArgs::default()
         };
-        let result = next_version(td.path(), &args).unwrap();
+        let result = next_version(td.path(), &GlobalArgs::default(), &args).unwrap();
 
         assert_eq!(result, Some("v1.0.1-alpha.1".to_string()));
     }
@@ -690,19 +2304,50 @@ mod tests {
         gitutils::commit(&repo, "fix: something").unwrap();
 
         let args = NextVersionArgs {
-            pattern: Some("v{major}.{minor}.{patch}".to_string()),
-            increment: Increment::Patch,
-            auto: false,
-            rule: vec![],
-            pre: Some("alpha".to_string()),
-            source: VersionSourceName::Tag,
-            create: false,
+            version: VersionArgs {
+                pattern: Some("v{major}.{minor}.{patch}".to_string()),
+                increment: Some(Increment::Patch),
+                pre: Some("alpha".to_string()),
+                source: Some(VersionSourceName::Tag),
+                ..VersionArgs::default()
+            },
+            ..NextVersionArgs::default()
         };
-        let result = next_version(td.path(), &args).unwrap();
+        let result = next_version(td.path(), &GlobalArgs::default(), &args).unwrap();
 
         assert_eq!(result, Some("v1.0.1-alpha.2".to_string()));
     }
 
+    #[test]
+    fn test_next_version_pre_release_moves_up_channel_on_same_version() {
+        let (td, repo) = testutils::init_repo();
+        let (_remote_td, mut remote) = testutils::init_remote(&repo);
+        for tag in ["v1.3.0", "v2.0.0-alpha.4"] {
+            create_new_remote_tag(&repo, &mut remote, tag, false);
+        }
+        gitutils::commit(&repo, "fix: something").unwrap();
+
+        let mut args = NextVersionArgs {
+            version: VersionArgs {
+                pre: Some("beta".to_string()),
+                ..VersionArgs::default()
+            },
+            create: true,
+            ..NextVersionArgs::default()
+        };
+        let result = next_version(td.path(), &GlobalArgs::default(), &args).unwrap();
+        assert_eq!(result, Some("v2.0.0-beta.1".to_string()));
+
+        args.version.pre = Some("alpha".to_string());
+        let result = next_version(td.path(), &GlobalArgs::default(), &args);
+        assert!(matches!(result, Err(FlophaError::ChannelRegression { .. })));
+
+        // With a custom order alpha is the most mature channel, so it may follow beta.
+        args.version.pre_channel_order = vec!["beta".to_string(), "alpha".to_string()];
+        let result = next_version(td.path(), &GlobalArgs::default(), &args).unwrap();
+        assert_eq!(result, Some("v2.0.0-alpha.5".to_string()));
+    }
+
     #[test]
     fn test_next_version_auto_with_custom_rules() {
         let (td, repo) = testutils::init_repo();
@@ -718,20 +2363,464 @@ mod tests {
         gitutils::commit(&repo, "feat: add thing").unwrap();
 
         let args = NextVersionArgs {
-            pattern: Some("v{major}.{minor}.{patch}".to_string()),
-            increment: Increment::Patch,
-            auto: true,
-            rule: vec!["major:BUMP_MAJOR:".to_string()],
-            pre: None,
-            source: VersionSourceName::Tag,
-            create: false,
+            version: VersionArgs {
+                pattern: Some("v{major}.{minor}.{patch}".to_string()),
+                increment: Some(Increment::Patch),
+                auto: true,
+                rule: vec!["major:BUMP_MAJOR:".to_string()],
+                source: Some(VersionSourceName::Tag),
+                ..VersionArgs::default()
+            },
+            ..NextVersionArgs::default()
         };
-        let result = next_version(td.path(), &args).unwrap();
+        let result = next_version(td.path(), &GlobalArgs::default(), &args).unwrap();
 
         // "feat:" doesn't match any custom rule → falls through to patch
         assert_eq!(result, Some("v1.0.1".to_string()));
     }
 
+    #[test]
+    fn test_next_version_push_publishes_tag_to_remote() {
+        let (td, repo) = testutils::init_repo();
+        let (remote_td, mut remote) = testutils::init_remote(&repo);
+        create_new_remote_tag(&repo, &mut remote, "v1.0.0", false);
+        gitutils::commit(&repo, "fix: something").unwrap();
+
+        let args = NextVersionArgs {
+            version: VersionArgs {
+                increment: Some(Increment::Patch),
+                source: Some(VersionSourceName::Tag),
+                ..VersionArgs::default()
+            },
+            create: true,
+            push: true,
+            ..NextVersionArgs::default()
+        };
+        let result = next_version(td.path(), &GlobalArgs::default(), &args).unwrap();
+        assert_eq!(result, Some("v1.0.1".to_string()));
+
+        let remote_repo = git2::Repository::open_bare(remote_td.path()).unwrap();
+        let remote_tag_id = remote_repo
+            .revparse_single("refs/tags/v1.0.1")
+            .unwrap()
+            .id();
+        let head_id = repo.head().unwrap().peel_to_commit().unwrap().id();
+        assert_eq!(remote_tag_id, head_id);
+    }
+
+    #[test]
+    fn test_next_version_push_publishes_branch_to_remote() {
+        let (td, repo) = testutils::init_repo();
+        let (remote_td, mut remote) = testutils::init_remote(&repo);
+        create_new_remote_branch(&repo, &mut remote, "release/1.0.0");
+        gitutils::commit(&repo, "New commit").unwrap();
+
+        let args = NextVersionArgs {
+            version: VersionArgs {
+                pattern: Some("release/{major}.{minor}.{patch}".to_string()),
+                increment: Some(Increment::Minor),
+                source: Some(VersionSourceName::Branch),
+                ..VersionArgs::default()
+            },
+            create: true,
+            push: true,
+            ..NextVersionArgs::default()
+        };
+        next_version(td.path(), &GlobalArgs::default(), &args).unwrap();
+
+        let remote_repo = git2::Repository::open_bare(remote_td.path()).unwrap();
+        assert!(remote_repo
+            .find_branch("release/1.1.0", git2::BranchType::Local)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_publish_version_reports_rejected_push() {
+        let (_td, repo) = testutils::init_repo();
+        let (_remote_td, mut remote) = testutils::init_remote(&repo);
+        create_new_remote_tag(&repo, &mut remote, "v1.0.0", true);
+
+        // Recreate the same tag locally on an older commit; moving a tag backwards is not
+        // a fast-forward, so the remote must refuse it.
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let parent_id = head.parent_id(0).unwrap();
+        gitutils::tag_oid(&repo, parent_id, "v1.0.0").unwrap();
+
+        let result = publish_version(&repo, &TagVersionSource::default(), "origin", "v1.0.0");
+        assert!(matches!(
+            result,
+            Err(FlophaError::PushRejected { ref name, ref remote, .. })
+                if name == "v1.0.0" && remote == "origin"
+        ));
+    }
+
+    #[test]
+    fn test_last_version_fetches_from_configured_remote() {
+        let (td, repo) = testutils::init_repo();
+        let (_remote_td, mut remote) = testutils::init_named_remote(&repo, "upstream");
+        create_new_remote_tag(&repo, &mut remote, "v1.2.0", true);
+
+        let global = GlobalArgs {
+            remote: "upstream".to_string(),
+            ..GlobalArgs::default()
+        };
+        let args = LastVersionArgs {
+            source: Some(VersionSourceName::Tag),
+            ..LastVersionArgs::default()
+        };
+        let result = last_version(td.path(), &global, &args).unwrap();
+
+        assert_eq!(result, Some("v1.2.0".to_string()));
+    }
+
+    #[test]
+    fn test_last_version_missing_remote_is_an_error() {
+        let (td, _repo) = testutils::init_repo();
+
+        let args = LastVersionArgs {
+            source: Some(VersionSourceName::Tag),
+            ..LastVersionArgs::default()
+        };
+        let result = last_version(td.path(), &GlobalArgs::default(), &args);
+
+        assert!(matches!(result, Err(FlophaError::RemoteNotFound { .. })));
+    }
+
+    #[test]
+    fn test_last_version_no_fetch_uses_local_refs() {
+        let (td, repo) = testutils::init_repo();
+        let commit_id = gitutils::commit(&repo, "New commit").unwrap();
+        gitutils::tag_oid(&repo, commit_id, "v1.0.0").unwrap();
+
+        let global = GlobalArgs {
+            no_fetch: true,
+            ..GlobalArgs::default()
+        };
+        let args = LastVersionArgs {
+            source: Some(VersionSourceName::Tag),
+            ..LastVersionArgs::default()
+        };
+        let result = last_version(td.path(), &global, &args).unwrap();
+
+        assert_eq!(result, Some("v1.0.0".to_string()));
+    }
+
+    #[test]
+    fn test_next_version_offline_tolerates_missing_remote() {
+        let (td, repo) = testutils::init_repo();
+        let commit_id = gitutils::commit(&repo, "New commit").unwrap();
+        gitutils::tag_oid(&repo, commit_id, "v1.0.0").unwrap();
+
+        let global = GlobalArgs {
+            offline: true,
+            ..GlobalArgs::default()
+        };
+        let args = NextVersionArgs {
+            version: VersionArgs {
+                increment: Some(Increment::Minor),
+                source: Some(VersionSourceName::Tag),
+                ..VersionArgs::default()
+            },
+            ..NextVersionArgs::default()
+        };
+        let result = next_version(td.path(), &global, &args).unwrap();
+
+        assert_eq!(result, Some("v1.1.0".to_string()));
+    }
+
+    #[test]
+    fn test_offline_refuses_to_push() {
+        let (td, repo) = testutils::init_repo();
+        let commit_id = gitutils::commit(&repo, "New commit").unwrap();
+        gitutils::tag_oid(&repo, commit_id, "v1.0.0").unwrap();
+
+        let global = GlobalArgs {
+            offline: true,
+            ..GlobalArgs::default()
+        };
+        let args = NextVersionArgs {
+            create: true,
+            push: true,
+            ..NextVersionArgs::default()
+        };
+        let err = next_version(td.path(), &global, &args).unwrap_err();
+
+        assert!(matches!(err, FlophaError::OfflinePush));
+        assert!(repo.find_reference("refs/tags/v1.0.1").is_err());
+    }
+
+    #[test]
+    fn test_next_version_reads_defaults_from_config_file() {
+        let (td, repo) = testutils::init_repo();
+        let (_remote_td, mut remote) = testutils::init_remote(&repo);
+        create_new_remote_tag(&repo, &mut remote, "desktop@1.0.0", false);
+        create_new_remote_tag(&repo, &mut remote, "v5.0.0", false);
+        gitutils::commit(&repo, "feat: add thing").unwrap();
+        std::fs::write(
+            td.path().join("flopha.toml"),
+            r#"
+pattern = "desktop@{major}.{minor}.{patch}"
+
+[next-version]
+auto = true
+"#,
+        )
+        .unwrap();
+
+        let mut args = NextVersionArgs::default();
+        let result = next_version(td.path(), &GlobalArgs::default(), &args).unwrap();
+        assert_eq!(result, Some("desktop@1.1.0".to_string()));
+
+        // Command-line flags win over the file.
+        args.version.increment = Some(Increment::Major);
+        let result = next_version(td.path(), &GlobalArgs::default(), &args).unwrap();
+        assert_eq!(result, Some("desktop@2.0.0".to_string()));
+    }
+
+    #[test]
+    fn test_invalid_config_file_is_reported() {
+        let (td, repo) = testutils::init_repo();
+        let (_remote_td, _remote) = testutils::init_remote(&repo);
+        std::fs::write(td.path().join("flopha.toml"), "source = \"svn\"\n").unwrap();
+
+        let args = LastVersionArgs::default();
+        let result = last_version(td.path(), &GlobalArgs::default(), &args);
+
+        assert!(matches!(
+            result,
+            Err(FlophaError::InvalidConfig { ref key, .. }) if key == "source"
+        ));
+    }
+
+    #[test]
+    fn test_next_version_uses_selected_stream() {
+        let (td, repo) = testutils::init_repo();
+        let (_remote_td, mut remote) = testutils::init_remote(&repo);
+        for tag in ["desktop@1.0.0", "api@3.2.0", "v9.0.0"] {
+            create_new_remote_tag(&repo, &mut remote, tag, false);
+        }
+        gitutils::commit(&repo, "feat: add endpoint").unwrap();
+        std::fs::write(
+            td.path().join("flopha.toml"),
+            r#"
+[streams.api]
+pattern = "api@{major}.{minor}.{patch}"
+rules = ["minor:^feat"]
+
+[streams.desktop]
+pattern = "desktop@{major}.{minor}.{patch}"
+"#,
+        )
+        .unwrap();
+
+        let global = GlobalArgs {
+            stream: Some("api".to_string()),
+            ..GlobalArgs::default()
+        };
+        let args = NextVersionArgs {
+            version: VersionArgs {
+                auto: true,
+                ..VersionArgs::default()
+            },
+            ..NextVersionArgs::default()
+        };
+        let result = next_version(td.path(), &global, &args).unwrap();
+        assert_eq!(result, Some("api@3.3.0".to_string()));
+
+        let global = GlobalArgs {
+            stream: Some("mobile".to_string()),
+            ..GlobalArgs::default()
+        };
+        let result = next_version(td.path(), &global, &args);
+        assert!(matches!(result, Err(FlophaError::StreamNotFound(_))));
+    }
+
+    #[test]
+    fn test_next_version_auto_only_considers_commits_touching_path() {
+        let (td, repo) = testutils::init_repo();
+        let (_remote_td, mut remote) = testutils::init_remote(&repo);
+        create_new_remote_tag(&repo, &mut remote, "desktop@1.0.0", false);
+        testutils::commit_file(&repo, "api/server.rs", "v2", "feat!: new api");
+        testutils::commit_file(&repo, "desktop/app.rs", "v2", "fix: desktop crash");
+
+        let mut args = NextVersionArgs {
+            version: VersionArgs {
+                pattern: Some("desktop@{major}.{minor}.{patch}".to_string()),
+                auto: true,
+                ..VersionArgs::default()
+            },
+            ..NextVersionArgs::default()
+        };
+        let result = next_version(td.path(), &GlobalArgs::default(), &args).unwrap();
+        assert_eq!(result, Some("desktop@2.0.0".to_string()));
+
+        args.version.path = vec!["desktop/".to_string()];
+        let result = next_version(td.path(), &GlobalArgs::default(), &args).unwrap();
+        assert_eq!(result, Some("desktop@1.0.1".to_string()));
+    }
+
+    #[test]
+    fn test_count_commits_between_filters_by_path() {
+        let (_td, repo) = testutils::init_repo();
+        let from = repo.head().unwrap().peel_to_commit().unwrap().id();
+        testutils::commit_file(&repo, "api/server.rs", "v2", "feat: api");
+        testutils::commit_file(&repo, "desktop/app.rs", "v2", "fix: desktop");
+        let to = testutils::commit_file(&repo, "api/client.rs", "v2", "fix: api");
+
+        let all = gitutils::count_commits_between(&repo, from, to, &[]).unwrap();
+        let api = gitutils::count_commits_between(&repo, from, to, &["api".to_string()]).unwrap();
+        let rust = gitutils::count_commits_between(&repo, from, to, &["*.rs".to_string()]).unwrap();
+
+        assert_eq!((all, api, rust), (3, 2, 3));
+    }
+
+    #[test]
+    fn test_changelog_groups_commits_per_release() {
+        let (td, repo) = testutils::init_repo();
+        let (_remote_td, mut remote) = testutils::init_remote(&repo);
+        create_new_remote_tag(&repo, &mut remote, "v1.0.0", false);
+        gitutils::commit(&repo, "feat: add export").unwrap();
+        let head = gitutils::commit(&repo, "fix(api): crash on empty body").unwrap();
+        gitutils::tag_oid(&repo, head, "v1.1.0").unwrap();
+        gitutils::commit(&repo, "perf: cache lookups").unwrap();
+
+        let args = ChangelogArgs {
+            since: Some("v1.0.0".to_string()),
+            ..ChangelogArgs::default()
+        };
+        let markdown = changelog(td.path(), &GlobalArgs::default(), &args).unwrap();
+
+        assert!(markdown.starts_with("## Unreleased\n\n### Performance\n\n- cache lookups ("));
+        let release = &markdown[markdown.find("## v1.1.0 (").unwrap()..];
+        assert!(release.contains("### Features\n\n- add export ("));
+        assert!(release.contains("### Bug Fixes\n\n- **api:** crash on empty body ("));
+        assert!(!markdown.contains("## v1.0.0"));
+
+        let args = ChangelogArgs {
+            since: Some("v0.9.0".to_string()),
+            ..args
+        };
+        let result = changelog(td.path(), &GlobalArgs::default(), &args);
+        assert!(matches!(result, Err(FlophaError::VersionNotFound(_))));
+    }
+
+    #[test]
+    fn test_changelog_unreleased_is_prepended_to_output_file() {
+        let (td, repo) = testutils::init_repo();
+        let (_remote_td, mut remote) = testutils::init_remote(&repo);
+        create_new_remote_tag(&repo, &mut remote, "v1.0.0", false);
+        gitutils::commit(&repo, "feat!: drop the v1 API").unwrap();
+        std::fs::write(
+            td.path().join("CHANGELOG.md"),
+            "# Changelog\n\n## v1.0.0 (2024-01-01)\n\n- first release\n",
+        )
+        .unwrap();
+
+        let args = ChangelogArgs {
+            unreleased: true,
+            output: Some("CHANGELOG.md".into()),
+            ..ChangelogArgs::default()
+        };
+        changelog(td.path(), &GlobalArgs::default(), &args).unwrap();
+
+        let content = std::fs::read_to_string(td.path().join("CHANGELOG.md")).unwrap();
+        assert!(content.starts_with(
+            "# Changelog\n\n## Unreleased\n\n### Breaking Changes\n\n- drop the v1 API ("
+        ));
+        assert!(content.ends_with("\n\n## v1.0.0 (2024-01-01)\n\n- first release\n"));
+        assert_eq!(content.matches("## v1.0.0").count(), 1);
+    }
+
+    #[test]
+    fn test_changelog_output_is_unchanged_when_regenerated() {
+        let (td, repo) = testutils::init_repo();
+        let (_remote_td, mut remote) = testutils::init_remote(&repo);
+        create_new_remote_tag(&repo, &mut remote, "v1.0.0", false);
+        gitutils::commit(&repo, "feat: add the v2 API").unwrap();
+
+        let args = ChangelogArgs {
+            output: Some("CHANGELOG.md".into()),
+            ..ChangelogArgs::default()
+        };
+        changelog(td.path(), &GlobalArgs::default(), &args).unwrap();
+        let first = std::fs::read_to_string(td.path().join("CHANGELOG.md")).unwrap();
+        changelog(td.path(), &GlobalArgs::default(), &args).unwrap();
+        let second = std::fs::read_to_string(td.path().join("CHANGELOG.md")).unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(second.matches("## Unreleased").count(), 1);
+        assert_eq!(second.matches("## v1.0.0").count(), 1);
+
+        // Once released, the Unreleased section gives way to the new version.
+        create_new_remote_tag(&repo, &mut remote, "v1.1.0", false);
+        changelog(td.path(), &GlobalArgs::default(), &args).unwrap();
+        let third = std::fs::read_to_string(td.path().join("CHANGELOG.md")).unwrap();
+        assert!(!third.contains("## Unreleased"));
+        assert_eq!(third.matches("## v1.1.0").count(), 1);
+        assert_eq!(third.matches("## v1.0.0").count(), 1);
+    }
+
+    #[test]
+    fn test_promote_tags_pre_release_commit() {
+        let (td, repo) = testutils::init_repo();
+        let (_remote_td, mut remote) = testutils::init_remote(&repo);
+        for tag in ["v2.3.0", "v2.4.0-beta.1", "v2.4.0-rc.3"] {
+            create_new_remote_tag(&repo, &mut remote, tag, false);
+        }
+        gitutils::commit(&repo, "fix: after rc").unwrap();
+
+        let args = PromoteArgs {
+            pre: Some("rc".to_string()),
+            ..PromoteArgs::default()
+        };
+        let result = promote(td.path(), &GlobalArgs::default(), &args).unwrap();
+        assert_eq!(result, Some("v2.4.0".to_string()));
+
+        let rc_commit = gitutils::tag_commit_oid(&repo, "v2.4.0-rc.3").unwrap();
+        let stable_commit = gitutils::tag_commit_oid(&repo, "v2.4.0").unwrap();
+        let head_id = repo.head().unwrap().peel_to_commit().unwrap().id();
+        assert_eq!(stable_commit, rc_commit);
+        assert_ne!(stable_commit, head_id);
+
+        // Promoting again must not move the existing stable tag.
+        let result = promote(td.path(), &GlobalArgs::default(), &args);
+        assert!(matches!(result, Err(FlophaError::TagExists(ref t)) if t == "v2.4.0"));
+    }
+
+    #[test]
+    fn test_promote_refuses_pre_release_older_than_stable() {
+        let (td, repo) = testutils::init_repo();
+        let (_remote_td, mut remote) = testutils::init_remote(&repo);
+        create_new_remote_tag(&repo, &mut remote, "v1.1.0-rc.1", false);
+        create_new_remote_tag(&repo, &mut remote, "v1.2.0", false);
+
+        let args = PromoteArgs::default();
+        let result = promote(td.path(), &GlobalArgs::default(), &args);
+        assert!(matches!(
+            result,
+            Err(FlophaError::PromotionNotNewer { ref pre_release, ref stable })
+                if pre_release == "v1.1.0-rc.1" && stable == "v1.2.0"
+        ));
+    }
+
+    #[test]
+    fn test_promote_without_pre_release_fails() {
+        let (td, repo) = testutils::init_repo();
+        let (_remote_td, mut remote) = testutils::init_remote(&repo);
+        create_new_remote_tag(&repo, &mut remote, "v1.0.0", false);
+        create_new_remote_tag(&repo, &mut remote, "v1.1.0-beta.1", false);
+
+        let args = PromoteArgs {
+            pre: Some("rc".to_string()),
+            ..PromoteArgs::default()
+        };
+        let result = promote(td.path(), &GlobalArgs::default(), &args);
+        assert!(matches!(
+            result,
+            Err(FlophaError::PreReleaseNotFound { channel: Some(ref c) }) if c == "rc"
+        ));
+    }
+
     fn create_new_remote_tag(
         repo: &git2::Repository,
         remote: &mut git2::Remote,
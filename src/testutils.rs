@@ -21,13 +21,17 @@ pub fn init_repo() -> (TempDir, Repository) {
 }
 
 pub fn init_remote(repo: &Repository) -> (TempDir, Remote<'_>) {
+    init_named_remote(repo, "origin")
+}
+
+pub fn init_named_remote<'a>(repo: &'a Repository, name: &str) -> (TempDir, Remote<'a>) {
     let td = TempDir::new().unwrap();
     let url = path2url(td.path());
     let mut opts = RepositoryInitOptions::new();
     opts.bare(true);
     opts.initial_head("main");
     Repository::init_opts(td.path(), &opts).unwrap();
-    let mut remote = repo.remote("origin", &url).unwrap();
+    let mut remote = repo.remote(name, &url).unwrap();
     let mut push_options = PushOptions::new();
     remote
         .push(&["refs/heads/main"], Some(&mut push_options))
@@ -35,6 +39,18 @@ pub fn init_remote(repo: &Repository) -> (TempDir, Remote<'_>) {
     (td, remote)
 }
 
+/// Writes `content` to `path` in the working directory, stages it and commits it.
+pub fn commit_file(repo: &Repository, path: &str, content: &str, message: &str) -> git2::Oid {
+    let workdir = repo.workdir().unwrap();
+    let file = workdir.join(path);
+    std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+    std::fs::write(&file, content).unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new(path)).unwrap();
+    index.write().unwrap();
+    commit(repo, message).unwrap()
+}
+
 fn path2url(path: &Path) -> String {
     Url::from_file_path(path).unwrap().to_string()
 }
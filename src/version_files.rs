@@ -0,0 +1,381 @@
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+
+use crate::error::FlophaError;
+
+/// A file whose version string `next-version --write-files` rewrites.
+///
+/// `Cargo.toml`, `package.json` and `pyproject.toml` are recognised by name and edited in
+/// place, keeping the rest of the file untouched. Any other file is a plain version file
+/// such as `VERSION`, unless a regex is given as `<path>:<regex>`, in which case the
+/// `version` capture group (or the first group) of every match is replaced.
+#[derive(Debug, Clone)]
+pub enum FileTarget {
+    /// `[package].version` of a Cargo manifest.
+    Cargo(PathBuf),
+    /// The top-level `version` of an npm manifest.
+    PackageJson(PathBuf),
+    /// `[project].version` of a Python project.
+    Pyproject(PathBuf),
+    /// A file containing nothing but the version.
+    Plain(PathBuf),
+    Regex {
+        path: PathBuf,
+        pattern: Regex,
+    },
+}
+
+/// The outcome of updating one [`FileTarget`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileChange {
+    pub path: PathBuf,
+    /// False when the file already had the version.
+    pub changed: bool,
+    /// The content before the version was written.
+    pub original: String,
+}
+
+impl FileTarget {
+    /// Parses `<path>` or `<path>:<regex>`. The colon of a Windows drive such as `C:\`
+    /// belongs to the path.
+    pub fn parse(spec: &str) -> Result<Self, FlophaError> {
+        let invalid = |reason: String| FlophaError::InvalidFileTarget {
+            input: spec.to_string(),
+            reason,
+        };
+        let drive = match spec.as_bytes() {
+            [letter, b':', b'\\' | b'/', ..] if letter.is_ascii_alphabetic() => 2,
+            _ => 0,
+        };
+        let separator = spec[drive..].find(':').map(|i| drive + i);
+        if let Some((path, pattern)) = separator.map(|i| (&spec[..i], &spec[i + 1..])) {
+            if path.is_empty() {
+                return Err(invalid(
+                    "expected format '<path>' or '<path>:<regex>'".to_string(),
+                ));
+            }
+            let pattern =
+                Regex::new(pattern).map_err(|e| invalid(format!("invalid regex: {}", e)))?;
+            if pattern.captures_len() < 2 {
+                return Err(invalid(
+                    "regex needs a capture group around the version, e.g. (?P<version>...)"
+                        .to_string(),
+                ));
+            }
+            return Ok(FileTarget::Regex {
+                path: PathBuf::from(path),
+                pattern,
+            });
+        }
+        let path = PathBuf::from(spec);
+        Ok(match path.file_name().and_then(|name| name.to_str()) {
+            Some("Cargo.toml") => FileTarget::Cargo(path),
+            Some("package.json") => FileTarget::PackageJson(path),
+            Some("pyproject.toml") => FileTarget::Pyproject(path),
+            _ => FileTarget::Plain(path),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        match self {
+            FileTarget::Cargo(path)
+            | FileTarget::PackageJson(path)
+            | FileTarget::Pyproject(path)
+            | FileTarget::Plain(path)
+            | FileTarget::Regex { path, .. } => path,
+        }
+    }
+
+    /// Returns `content` with its version replaced by `version`.
+    fn apply(&self, content: &str, version: &str) -> Result<String, String> {
+        let spans = match self {
+            FileTarget::Cargo(_) => vec![toml_string_span(content, "package", "version")
+                .ok_or("no version string in the [package] table")?],
+            FileTarget::Pyproject(_) => vec![toml_string_span(content, "project", "version")
+                .ok_or("no version string in the [project] table")?],
+            FileTarget::PackageJson(_) => {
+                vec![json_version_span(content).ok_or("no top-level \"version\" string")?]
+            }
+            FileTarget::Plain(_) => {
+                let start = content.len() - content.trim_start().len();
+                let end = content.trim_end().len().max(start);
+                std::iter::once(start..end).collect()
+            }
+            FileTarget::Regex { pattern, .. } => {
+                let spans: Vec<Range<usize>> = pattern
+                    .captures_iter(content)
+                    .filter_map(|caps| caps.name("version").or_else(|| caps.get(1)))
+                    .map(|m| m.range())
+                    .collect();
+                if spans.is_empty() {
+                    return Err(format!("regex '{}' does not match", pattern));
+                }
+                spans
+            }
+        };
+        let mut out = String::with_capacity(content.len());
+        let mut last = 0;
+        for span in spans {
+            out.push_str(&content[last..span.start]);
+            out.push_str(version);
+            last = span.end;
+        }
+        out.push_str(&content[last..]);
+        Ok(out)
+    }
+}
+
+/// Writes `version` into every target under `root`.
+///
+/// All targets are read and rewritten in memory first, so a missing file or version
+/// leaves every file untouched; if writing fails half-way, the files already written
+/// are restored.
+pub fn write_version(
+    root: &Path,
+    targets: &[FileTarget],
+    version: &str,
+) -> Result<Vec<FileChange>, FlophaError> {
+    let mut updates = Vec::new();
+    for target in targets {
+        let file = root.join(target.path());
+        let failed = |reason: String| FlophaError::VersionFile {
+            path: target.path().display().to_string(),
+            reason,
+        };
+        let content = std::fs::read_to_string(&file).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => failed("file not found".to_string()),
+            _ => failed(e.to_string()),
+        })?;
+        let updated = target.apply(&content, version).map_err(failed)?;
+        updates.push((target.path().to_path_buf(), file, content, updated));
+    }
+
+    let mut written: Vec<(&PathBuf, &String)> = Vec::new();
+    for (_, file, content, updated) in &updates {
+        if content == updated {
+            continue;
+        }
+        if let Err(source) = std::fs::write(file, updated) {
+            for (file, content) in written {
+                let _ = std::fs::write(file, content);
+            }
+            return Err(FlophaError::Io {
+                path: file.display().to_string(),
+                source,
+            });
+        }
+        written.push((file, content));
+    }
+
+    Ok(updates
+        .into_iter()
+        .map(|(path, _, content, updated)| FileChange {
+            path,
+            changed: content != updated,
+            original: content,
+        })
+        .collect())
+}
+
+/// Puts back the original content of the files changed by [`write_version`].
+pub fn restore(root: &Path, changes: &[FileChange]) -> Result<(), FlophaError> {
+    for change in changes.iter().filter(|change| change.changed) {
+        let file = root.join(&change.path);
+        std::fs::write(&file, &change.original).map_err(|source| FlophaError::Io {
+            path: file.display().to_string(),
+            source,
+        })?;
+    }
+    Ok(())
+}
+
+/// Finds the quoted value of `key = "..."` directly inside the TOML table `[table]`.
+fn toml_string_span(content: &str, table: &str, key: &str) -> Option<Range<usize>> {
+    let value = Regex::new(&format!(
+        r#"^\s*{}\s*=\s*(?:"(?P<basic>[^"]*)"|'(?P<literal>[^']*)')"#,
+        regex::escape(key)
+    ))
+    .unwrap();
+    let mut in_table = false;
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            let header = trimmed.split('#').next().unwrap_or_default().trim();
+            in_table = header == format!("[{}]", table);
+        } else if in_table {
+            if let Some(caps) = value.captures(line) {
+                let m = caps.name("basic").or_else(|| caps.name("literal"))?;
+                return Some(offset + m.start()..offset + m.end());
+            }
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// Finds the string value of the top-level `"version"` key of a JSON document without
+/// re-serializing it, so the file's formatting and key order are preserved.
+fn json_version_span(content: &str) -> Option<Range<usize>> {
+    let bytes = content.as_bytes();
+    let mut depth = 0;
+    let mut i = 0;
+    // Set after the top-level "version" key; the next string at depth 1 is its value.
+    let mut expect_value = false;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'{' | b'[' => {
+                depth += 1;
+                expect_value = false;
+            }
+            b'}' | b']' => depth -= 1,
+            b'"' => {
+                let start = i + 1;
+                let mut end = start;
+                while end < bytes.len() && bytes[end] != b'"' {
+                    end += if bytes[end] == b'\\' { 2 } else { 1 };
+                }
+                if end >= bytes.len() {
+                    return None;
+                }
+                if depth == 1 && expect_value {
+                    return Some(start..end);
+                }
+                if depth == 1 && &content[start..end] == "version" {
+                    let rest = content[end + 1..].trim_start();
+                    expect_value = rest.starts_with(':');
+                }
+                i = end;
+            }
+            b',' => expect_value = false,
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn apply(spec: &str, content: &str) -> Result<String, String> {
+        FileTarget::parse(spec).unwrap().apply(content, "1.4.0")
+    }
+
+    #[test]
+    fn test_cargo_toml_only_changes_package_version() {
+        let content = "[package]\nname = \"app\"\nversion = \"1.3.2\" # bump\n\n\
+                       [dependencies]\nregex = { version = \"1\" }\n";
+        assert_eq!(
+            apply("Cargo.toml", content).unwrap(),
+            "[package]\nname = \"app\"\nversion = \"1.4.0\" # bump\n\n\
+             [dependencies]\nregex = { version = \"1\" }\n"
+        );
+        assert!(apply("Cargo.toml", "[workspace]\nversion = \"1\"\n").is_err());
+    }
+
+    #[test]
+    fn test_pyproject_changes_project_version() {
+        let content = "[tool.other]\nversion = \"0\"\n\n[project]\nversion = '1.3.2'\n";
+        assert_eq!(
+            apply("pyproject.toml", content).unwrap(),
+            "[tool.other]\nversion = \"0\"\n\n[project]\nversion = '1.4.0'\n"
+        );
+    }
+
+    #[test]
+    fn test_package_json_changes_top_level_version() {
+        let content = "{\n  \"name\": \"app\",\n  \"engines\": { \"version\": \"x\" },\n  \
+                       \"description\": \"\\\"version\\\"\",\n  \"version\": \"1.3.2\"\n}\n";
+        assert_eq!(
+            apply("web/package.json", content).unwrap(),
+            "{\n  \"name\": \"app\",\n  \"engines\": { \"version\": \"x\" },\n  \
+             \"description\": \"\\\"version\\\"\",\n  \"version\": \"1.4.0\"\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_plain_and_regex_targets() {
+        assert_eq!(apply("VERSION", "1.3.2\n").unwrap(), "1.4.0\n");
+        assert_eq!(
+            apply(
+                r#"src/version.rs:VERSION: &str = "(?P<version>[^"]+)""#,
+                "pub const VERSION: &str = \"1.3.2\";\n"
+            )
+            .unwrap(),
+            "pub const VERSION: &str = \"1.4.0\";\n"
+        );
+        assert!(apply(r"src/version.rs:VERSION = (\d+)", "").is_err());
+        assert!(matches!(
+            FileTarget::parse("version.rs:VERSION = .*"),
+            Err(FlophaError::InvalidFileTarget { .. })
+        ));
+    }
+
+    #[test]
+    fn test_windows_drive_is_part_of_the_path() {
+        let target = FileTarget::parse(r"C:\repo\VERSION").unwrap();
+        assert!(matches!(target, FileTarget::Plain(_)));
+        assert_eq!(target.path(), Path::new(r"C:\repo\VERSION"));
+        assert!(matches!(
+            FileTarget::parse("C:/repo/Cargo.toml").unwrap(),
+            FileTarget::Cargo(_)
+        ));
+
+        let target = FileTarget::parse(r"D:\repo\app.txt:v(?P<version>\S+)").unwrap();
+        assert!(matches!(target, FileTarget::Regex { .. }));
+        assert_eq!(target.path(), Path::new(r"D:\repo\app.txt"));
+    }
+
+    #[test]
+    fn test_write_version_is_all_or_nothing() {
+        let td = TempDir::new().unwrap();
+        std::fs::write(td.path().join("VERSION"), "1.3.2\n").unwrap();
+        let targets = vec![
+            FileTarget::parse("VERSION").unwrap(),
+            FileTarget::parse("Cargo.toml").unwrap(),
+        ];
+        let result = write_version(td.path(), &targets, "1.4.0");
+        assert!(matches!(result, Err(FlophaError::VersionFile { .. })));
+        assert_eq!(
+            std::fs::read_to_string(td.path().join("VERSION")).unwrap(),
+            "1.3.2\n"
+        );
+
+        std::fs::write(
+            td.path().join("Cargo.toml"),
+            "[package]\nversion = \"1.4.0\"\n",
+        )
+        .unwrap();
+        let changes = write_version(td.path(), &targets, "1.4.0").unwrap();
+        assert_eq!(
+            changes,
+            vec![
+                FileChange {
+                    path: PathBuf::from("VERSION"),
+                    changed: true,
+                    original: "1.3.2\n".to_string(),
+                },
+                FileChange {
+                    path: PathBuf::from("Cargo.toml"),
+                    changed: false,
+                    original: "[package]\nversion = \"1.4.0\"\n".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            std::fs::read_to_string(td.path().join("VERSION")).unwrap(),
+            "1.4.0\n"
+        );
+
+        restore(td.path(), &changes).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(td.path().join("VERSION")).unwrap(),
+            "1.3.2\n"
+        );
+    }
+}
@@ -1,21 +1,109 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::error::FlophaError;
 use crate::gitutils;
 use git2::Repository;
 
 pub trait VersionSource {
     fn fetch_all(&self, repo: &Repository) -> Vec<String>;
     fn checkout(&self, repo: &Repository, version: &str) -> Result<(), git2::Error>;
-    fn create(&self, repo: &Repository, version: &str) -> Result<(), git2::Error>;
+    /// The full name of the reference for `version`, e.g. `refs/tags/v1.2.3`.
+    fn refname(&self, version: &str) -> String;
+    /// Returns the commit the tag or branch `version` points to.
+    fn commit_id(&self, repo: &Repository, version: &str) -> Result<git2::Oid, git2::Error>;
+    /// Creates the tag or branch `version` pointing at the commit `target`.
+    fn create(
+        &self,
+        repo: &Repository,
+        version: &str,
+        target: git2::Oid,
+    ) -> Result<(), FlophaError>;
+    fn publish(
+        &self,
+        repo: &Repository,
+        remote: &mut git2::Remote,
+        version: &str,
+    ) -> Result<(), git2::Error>;
+    /// Makes the refs of `versions` and their history available locally, for the
+    /// commands that walk commits. Sources reading local refs have nothing to do.
+    fn fetch_versions(&self, _repo: &Repository, _versions: &[String]) -> Result<(), git2::Error> {
+        Ok(())
+    }
+}
+
+/// How new tags are written: lightweight by default, annotated when a message is
+/// requested, and signed on top of that with `sign`.
+#[derive(Debug, Default, Clone)]
+pub struct TagOptions {
+    pub annotate: bool,
+    /// Tag message; `{version}` is replaced with the tag name. Implies `annotate`.
+    pub message: Option<String>,
+    /// Sign the tag with the user's configured key. Implies `annotate`.
+    pub sign: bool,
+}
+
+impl TagOptions {
+    pub fn is_annotated(&self) -> bool {
+        self.annotate || self.message.is_some() || self.sign
+    }
+
+    fn render_message(&self, version: &str) -> String {
+        self.message
+            .as_deref()
+            .unwrap_or("{version}")
+            .replace("{version}", version)
+    }
 }
 
-pub struct TagVersionSource;
-pub struct BranchVersionSource;
+#[derive(Default)]
+pub struct TagVersionSource {
+    options: TagOptions,
+    /// When set, only tags on this commit or its ancestors are listed.
+    reachable_from: Option<git2::Oid>,
+}
+
+impl TagVersionSource {
+    pub fn new(options: TagOptions) -> Self {
+        Self {
+            options,
+            reachable_from: None,
+        }
+    }
+
+    /// Limits the listed tags to those reachable from `commit`, so a maintenance branch
+    /// only sees the versions of its own line.
+    pub fn reachable_from(mut self, commit: Option<git2::Oid>) -> Self {
+        self.reachable_from = commit;
+        self
+    }
+
+    fn is_reachable(&self, repo: &Repository, tag: &str) -> bool {
+        let Some(from) = self.reachable_from else {
+            return true;
+        };
+        gitutils::tag_commit_oid(repo, tag)
+            .and_then(|commit| gitutils::is_ancestor(repo, commit, from))
+            .unwrap_or_else(|e| {
+                log::debug!("Skipping tag '{}': {}", tag, e);
+                false
+            })
+    }
+}
+
+/// Versions read from branch names: local branches plus the remote-tracking branches of
+/// `remote`, so a fresh clone sees every `release/*` branch of the remote.
+pub struct BranchVersionSource {
+    remote: String,
+}
 
 impl VersionSource for TagVersionSource {
     fn fetch_all(&self, repo: &Repository) -> Vec<String> {
         match repo.tag_names(Some("*")) {
             Ok(names) => names
                 .iter()
-                .filter_map(|s| s.map(|s| s.to_string()))
+                .flatten()
+                .filter(|name| self.is_reachable(repo, name))
+                .map(|name| name.to_string())
                 .collect(),
             Err(e) => {
                 log::warn!("Failed to fetch tags: {}", e);
@@ -28,32 +116,292 @@ impl VersionSource for TagVersionSource {
         gitutils::checkout_tag(repo, version)
     }
 
-    fn create(&self, repo: &Repository, version: &str) -> Result<(), git2::Error> {
-        let commit = repo.head()?.peel_to_commit()?;
-        gitutils::tag_oid(repo, commit.id(), version)?;
+    fn refname(&self, version: &str) -> String {
+        format!("refs/tags/{}", version)
+    }
+
+    fn commit_id(&self, repo: &Repository, version: &str) -> Result<git2::Oid, git2::Error> {
+        gitutils::tag_commit_oid(repo, version)
+    }
+
+    fn create(
+        &self,
+        repo: &Repository,
+        version: &str,
+        target: git2::Oid,
+    ) -> Result<(), FlophaError> {
+        if self.options.is_annotated() {
+            let message = self.options.render_message(version);
+            gitutils::annotated_tag_oid(repo, target, version, &message, self.options.sign)?;
+        } else {
+            gitutils::tag_oid(repo, target, version)?;
+        }
         Ok(())
     }
+
+    fn publish(
+        &self,
+        _repo: &Repository,
+        remote: &mut git2::Remote,
+        version: &str,
+    ) -> Result<(), git2::Error> {
+        gitutils::push_tag(remote, version)
+    }
+}
+
+impl BranchVersionSource {
+    pub fn new(remote: &str) -> Self {
+        Self {
+            remote: remote.to_string(),
+        }
+    }
+
+    /// Lists `(name, branch)` pairs for the branches of `kind`. Remote-tracking branches
+    /// are named without their remote, so `origin/release/1.2.0` is `release/1.2.0`, and
+    /// only those of `self.remote` are listed.
+    fn branches<'r>(
+        &self,
+        repo: &'r Repository,
+        kind: git2::BranchType,
+    ) -> Result<Vec<(String, git2::Branch<'r>)>, git2::Error> {
+        let prefix = format!("refs/remotes/{}/", self.remote);
+        let mut branches = Vec::new();
+        for branch in repo.branches(Some(kind))? {
+            let (branch, _) = branch?;
+            let name = match kind {
+                git2::BranchType::Local => branch.name()?.map(str::to_string),
+                git2::BranchType::Remote => branch
+                    .get()
+                    .name()
+                    .and_then(|refname| refname.strip_prefix(&prefix))
+                    .map(str::to_string),
+            };
+            match name {
+                Some(name) if name != "HEAD" => branches.push((name, branch)),
+                _ => {}
+            }
+        }
+        Ok(branches)
+    }
+
+    /// Finds the local branch `name`, or else the remote-tracking branch of that name.
+    fn find<'r>(&self, repo: &'r Repository, name: &str) -> Result<git2::Branch<'r>, git2::Error> {
+        repo.find_branch(name, git2::BranchType::Local)
+            .or_else(|_| {
+                repo.find_branch(
+                    &format!("{}/{}", self.remote, name),
+                    git2::BranchType::Remote,
+                )
+            })
+    }
 }
 
 impl VersionSource for BranchVersionSource {
     fn fetch_all(&self, repo: &Repository) -> Vec<String> {
-        match repo.branches(Some(git2::BranchType::Local)) {
-            Ok(branches) => branches
-                .filter_map(|b| b.ok())
-                .filter_map(|(branch, _)| branch.name().ok().flatten().map(|s| s.to_string()))
-                .collect(),
-            Err(e) => {
-                log::warn!("Failed to fetch branches: {}", e);
-                Vec::new()
+        let mut names = Vec::new();
+        let mut seen = HashSet::new();
+        for kind in [git2::BranchType::Local, git2::BranchType::Remote] {
+            match self.branches(repo, kind) {
+                Ok(branches) => names.extend(
+                    branches
+                        .into_iter()
+                        .map(|(name, _)| name)
+                        .filter(|name| seen.insert(name.clone())),
+                ),
+                Err(e) => log::warn!("Failed to fetch branches: {}", e),
             }
         }
+        names
     }
 
     fn checkout(&self, repo: &Repository, version: &str) -> Result<(), git2::Error> {
+        if repo.find_branch(version, git2::BranchType::Local).is_err() {
+            let upstream = format!("{}/{}", self.remote, version);
+            let commit = repo
+                .find_branch(&upstream, git2::BranchType::Remote)?
+                .get()
+                .peel_to_commit()?;
+            log::debug!("Creating branch '{}' tracking '{}'", version, upstream);
+            repo.branch(version, &commit, false)?
+                .set_upstream(Some(&upstream))?;
+        }
         gitutils::checkout_branch(repo, version, false)
     }
 
-    fn create(&self, repo: &Repository, version: &str) -> Result<(), git2::Error> {
-        gitutils::checkout_branch(repo, version, true)
+    fn refname(&self, version: &str) -> String {
+        format!("refs/heads/{}", version)
+    }
+
+    fn commit_id(&self, repo: &Repository, version: &str) -> Result<git2::Oid, git2::Error> {
+        let branch = self.find(repo, version)?;
+        Ok(branch.get().peel_to_commit()?.id())
+    }
+
+    fn create(
+        &self,
+        repo: &Repository,
+        version: &str,
+        target: git2::Oid,
+    ) -> Result<(), FlophaError> {
+        if repo.find_branch(version, git2::BranchType::Local).is_ok() {
+            return Err(FlophaError::BranchExists(version.to_string()));
+        }
+        let commit = repo.find_commit(target)?;
+        repo.branch(version, &commit, false)?;
+        gitutils::checkout_branch(repo, version, false)?;
+        Ok(())
+    }
+
+    fn publish(
+        &self,
+        repo: &Repository,
+        remote: &mut git2::Remote,
+        version: &str,
+    ) -> Result<(), git2::Error> {
+        let mut branch = repo.find_branch(version, git2::BranchType::Local)?;
+        gitutils::push_branch(remote, &mut branch)
+    }
+}
+
+/// Versions read from the tags and branches the remote advertises, like `git ls-remote`,
+/// so large repositories learn their versions without a full fetch. Tags win over
+/// branches of the same name. New versions are created as tags.
+pub struct RemoteVersionSource {
+    remote: String,
+    options: TagOptions,
+    /// Advertised refs by version name: the full refname and its commit.
+    refs: HashMap<String, (String, git2::Oid)>,
+}
+
+impl RemoteVersionSource {
+    /// Reads the versions `remote` advertises, like `git ls-remote`, without downloading
+    /// any object.
+    pub fn list(repo: &Repository, remote: &str, options: TagOptions) -> Result<Self, FlophaError> {
+        let listed = gitutils::list_remote_refs(&mut gitutils::get_remote(repo, remote)?)?;
+        Ok(Self::from_refs(remote, options, &listed))
+    }
+
+    /// Reads the versions of `remote` as of its last fetch, from the local tags and
+    /// remote-tracking branches, without contacting it.
+    pub fn offline(
+        repo: &Repository,
+        remote: &str,
+        options: TagOptions,
+    ) -> Result<Self, FlophaError> {
+        let tracking = format!("refs/remotes/{}/", remote);
+        let mut listed = Vec::new();
+        for reference in repo.references()? {
+            let reference = reference?;
+            let (Some(refname), Ok(commit)) = (reference.name(), reference.peel_to_commit()) else {
+                continue;
+            };
+            if refname.starts_with("refs/tags/") {
+                listed.push((refname.to_string(), commit.id()));
+            } else if let Some(branch) = refname.strip_prefix(&tracking) {
+                if branch != "HEAD" {
+                    listed.push((format!("refs/heads/{}", branch), commit.id()));
+                }
+            }
+        }
+        Ok(Self::from_refs(remote, options, &listed))
+    }
+
+    fn from_refs(remote: &str, options: TagOptions, listed: &[(String, git2::Oid)]) -> Self {
+        let mut refs = HashMap::new();
+        for prefix in ["refs/tags/", "refs/heads/"] {
+            for (refname, oid) in listed {
+                if let Some(name) = refname.strip_prefix(prefix) {
+                    refs.entry(name.to_string())
+                        .or_insert_with(|| (refname.clone(), *oid));
+                }
+            }
+        }
+        Self {
+            remote: remote.to_string(),
+            options,
+            refs,
+        }
+    }
+
+    /// The local ref a fetched version is stored under: tags as they are, branches as
+    /// remote-tracking branches.
+    fn local_refname(&self, refname: &str) -> String {
+        match refname.strip_prefix("refs/heads/") {
+            Some(branch) => format!("refs/remotes/{}/{}", self.remote, branch),
+            None => refname.to_string(),
+        }
+    }
+
+    fn is_branch(&self, version: &str) -> bool {
+        self.refs
+            .get(version)
+            .is_some_and(|(refname, _)| refname.starts_with("refs/heads/"))
+    }
+}
+
+impl VersionSource for RemoteVersionSource {
+    fn fetch_all(&self, _repo: &Repository) -> Vec<String> {
+        self.refs.keys().cloned().collect()
+    }
+
+    fn checkout(&self, repo: &Repository, version: &str) -> Result<(), git2::Error> {
+        self.fetch_versions(repo, &[version.to_string()])?;
+        if self.is_branch(version) {
+            BranchVersionSource::new(&self.remote).checkout(repo, version)
+        } else {
+            gitutils::checkout_tag(repo, version)
+        }
+    }
+
+    fn refname(&self, version: &str) -> String {
+        match self.refs.get(version) {
+            Some((refname, _)) => refname.clone(),
+            None => format!("refs/tags/{}", version),
+        }
+    }
+
+    fn commit_id(&self, repo: &Repository, version: &str) -> Result<git2::Oid, git2::Error> {
+        match self.refs.get(version) {
+            Some((_, oid)) => Ok(*oid),
+            None => gitutils::tag_commit_oid(repo, version),
+        }
+    }
+
+    fn create(
+        &self,
+        repo: &Repository,
+        version: &str,
+        target: git2::Oid,
+    ) -> Result<(), FlophaError> {
+        TagVersionSource::new(self.options.clone()).create(repo, version, target)
+    }
+
+    fn publish(
+        &self,
+        _repo: &Repository,
+        remote: &mut git2::Remote,
+        version: &str,
+    ) -> Result<(), git2::Error> {
+        gitutils::push_tag(remote, version)
+    }
+
+    fn fetch_versions(&self, repo: &Repository, versions: &[String]) -> Result<(), git2::Error> {
+        let refspecs: Vec<String> = versions
+            .iter()
+            .filter_map(|version| self.refs.get(version))
+            .filter_map(|(refname, oid)| {
+                let local = self.local_refname(refname);
+                let present = repo
+                    .revparse_single(&local)
+                    .and_then(|object| object.peel_to_commit())
+                    .is_ok_and(|commit| commit.id() == *oid);
+                (!present).then(|| format!("+{}:{}", refname, local))
+            })
+            .collect();
+        if refspecs.is_empty() {
+            return Ok(());
+        }
+        let mut remote = repo.find_remote(&self.remote)?;
+        gitutils::fetch_refs(&mut remote, &refspecs)
     }
 }
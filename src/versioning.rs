@@ -1,4 +1,6 @@
-use clap::ValueEnum;
+use std::ops::Range;
+use std::str::FromStr;
+
 use regex::Regex;
 
 use crate::error::FlophaError;
@@ -34,61 +36,613 @@ pub fn conventional_bump_rules() -> Vec<BumpRule> {
     ]
 }
 
+/// Parses a `<level>:<regex>` rule such as `minor:^feat` into a [`BumpRule`].
+pub fn parse_bump_rule(s: &str) -> Result<BumpRule, FlophaError> {
+    let (level, pattern) = s.split_once(':').ok_or_else(|| FlophaError::InvalidRule {
+        input: s.to_string(),
+        reason: "expected format '<level>:<pattern>'".to_string(),
+    })?;
+    let increment = match level {
+        "major" => Increment::Major,
+        "minor" => Increment::Minor,
+        "patch" => Increment::Patch,
+        other => {
+            return Err(FlophaError::InvalidRule {
+                input: s.to_string(),
+                reason: format!("unknown level '{}', expected major, minor, or patch", other),
+            })
+        }
+    };
+    BumpRule::new(pattern, increment).map_err(|e| FlophaError::InvalidRule {
+        input: s.to_string(),
+        reason: format!("invalid regex: {}", e),
+    })
+}
+
+/// Returns the rule deciding the bump of `message`: the first matching major rule,
+/// else the first matching minor rule, else the first other match.
+pub fn matching_rule<'r>(message: &str, rules: &'r [BumpRule]) -> Option<&'r BumpRule> {
+    let mut matched: Option<&BumpRule> = None;
+    for rule in rules.iter().filter(|rule| rule.pattern.is_match(message)) {
+        match rule.increment {
+            Increment::Major => return Some(rule),
+            Increment::Minor => {
+                if !matched.is_some_and(|m| m.increment == Increment::Minor) {
+                    matched = Some(rule);
+                }
+            }
+            Increment::Patch | Increment::Component(_) => matched = matched.or(Some(rule)),
+        }
+    }
+    matched
+}
+
+/// Returns the highest [`Increment`] among the `rules` matching `message`, or `None`
+/// when no rule matches.
+pub fn classify_commit(message: &str, rules: &[BumpRule]) -> Option<Increment> {
+    matching_rule(message, rules).map(|rule| rule.increment.clone())
+}
+
+/// How [`detect_increment`] classified one commit message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommitBump {
+    /// The first line of the message.
+    pub subject: String,
+    /// Pattern and level of the rule that decided the commit; `None` when none matched.
+    pub rule: Option<(String, Increment)>,
+}
+
+/// The bump inferred by [`detect_increment`], with the classification of every message
+/// that led to it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BumpReport {
+    pub increment: Increment,
+    /// One entry per message, in the order given.
+    pub commits: Vec<CommitBump>,
+}
+
 /// Infers the highest-priority [`Increment`] from `messages` using `rules`.
 ///
 /// Every rule is tested against every message independently; the highest-priority
-/// match across the whole set wins (major > minor > patch).  Returns `Patch` when
-/// nothing matches.
-pub fn detect_increment(messages: &[String], rules: &[BumpRule]) -> Increment {
-    let mut result = Increment::Patch;
+/// match across the whole set wins (major > minor > patch).  The increment is `Patch`
+/// when nothing matches.
+pub fn detect_increment(messages: &[String], rules: &[BumpRule]) -> BumpReport {
+    let mut increment = Increment::Patch;
+    let mut commits = Vec::new();
     for message in messages {
-        for rule in rules {
-            if rule.pattern.is_match(message) {
-                match rule.increment {
-                    Increment::Major => return Increment::Major,
-                    Increment::Minor => result = Increment::Minor,
-                    Increment::Patch => {}
-                }
-            }
+        let rule = matching_rule(message, rules);
+        match rule.map(|rule| &rule.increment) {
+            Some(Increment::Major) => increment = Increment::Major,
+            Some(Increment::Minor) if increment != Increment::Major => increment = Increment::Minor,
+            _ => {}
         }
+        commits.push(CommitBump {
+            subject: message.lines().next().unwrap_or_default().to_string(),
+            rule: rule.map(|rule| (rule.pattern.as_str().to_string(), rule.increment.clone())),
+        });
     }
-    result
+    BumpReport { increment, commits }
 }
 
 pub struct Versioner {
     tags: Vec<String>,
     pattern: String,
+    /// The date CalVer placeholders are filled from; `None` means today.
+    today: Option<CalendarDate>,
+    /// Stands in for the last version while no tag matches the pattern.
+    initial: Option<Version>,
+}
+
+/// One dot-separated identifier of a SemVer pre-release, e.g. `rc` or `2` in `rc.2`.
+///
+/// The derived ordering follows SemVer 2.0: numeric identifiers compare numerically and
+/// always have lower precedence than alphanumeric ones, which compare lexically.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub enum Identifier {
+    Numeric(u64),
+    AlphaNumeric(String),
+}
+
+impl Identifier {
+    pub fn parse(s: &str) -> Self {
+        if !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()) {
+            if let Ok(n) = s.parse::<u64>() {
+                return Identifier::Numeric(n);
+            }
+        }
+        Identifier::AlphaNumeric(s.to_string())
+    }
+}
+
+impl std::fmt::Display for Identifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Identifier::Numeric(n) => write!(f, "{}", n),
+            Identifier::AlphaNumeric(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// Parses a dot-separated pre-release string such as `rc.2` into identifiers.
+pub fn parse_pre_release(s: &str) -> Vec<Identifier> {
+    s.split('.').map(Identifier::parse).collect()
+}
+
+/// The calendar components of a CalVer version. Fields run from most to least
+/// significant, so the derived ordering is chronological; components the pattern does
+/// not use are `None`.
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub struct CalendarDate {
+    /// The full year, also for `{yy}` and `{0y}` (`26` is stored as `2026`).
+    pub year: Option<u32>,
+    pub month: Option<u32>,
+    /// Week of the year, counting from January 1st (1-53).
+    pub week: Option<u32>,
+    pub day: Option<u32>,
+}
+
+impl CalendarDate {
+    pub fn new(year: u32, month: u32, day: u32) -> Self {
+        let ordinal = days_from_civil(year as i64, month, day) - days_from_civil(year as i64, 1, 1);
+        Self {
+            year: Some(year),
+            month: Some(month),
+            week: Some(ordinal as u32 / 7 + 1),
+            day: Some(day),
+        }
+    }
+
+    /// The UTC date of a Unix timestamp.
+    pub fn from_timestamp(ts: i64) -> Self {
+        let (year, month, day) = civil_from_days(ts.div_euclid(86400));
+        Self::new(year.max(0) as u32, month, day)
+    }
+
+    /// Today's UTC date, or the date of `SOURCE_DATE_EPOCH` when it is set so that
+    /// builds can be reproduced.
+    pub fn today() -> Self {
+        let ts = std::env::var("SOURCE_DATE_EPOCH")
+            .ok()
+            .and_then(|epoch| epoch.trim().parse::<i64>().ok())
+            .unwrap_or_else(|| {
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map_or(0, |d| d.as_secs() as i64)
+            });
+        Self::from_timestamp(ts)
+    }
+
+    /// Formats the date as `YYYY-MM-DD`.
+    pub fn to_iso(&self) -> String {
+        format!(
+            "{:04}-{:02}-{:02}",
+            self.year.unwrap_or(0),
+            self.month.unwrap_or(0),
+            self.day.unwrap_or(0)
+        )
+    }
+}
+
+/// Converts days since 1970-01-01 to a `(year, month, day)` Gregorian date.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Converts a Gregorian date to days since 1970-01-01.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 } as i64;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// A CalVer placeholder of a version pattern, following <https://calver.org>.
+#[derive(Debug, Clone, Copy)]
+enum CalendarPart {
+    /// `{yyyy}`: `2026`.
+    FullYear,
+    /// `{yy}`: `26`, years since 2000.
+    ShortYear,
+    /// `{0y}`: `06`, zero-padded `{yy}`.
+    PaddedYear,
+    /// `{mm}`: `1` to `12`.
+    Month,
+    /// `{0m}`: `01` to `12`.
+    PaddedMonth,
+    /// `{ww}`: `1` to `53`.
+    Week,
+    /// `{0w}`: `01` to `53`.
+    PaddedWeek,
+    /// `{dd}`: `1` to `31`.
+    Day,
+    /// `{0d}`: `01` to `31`.
+    PaddedDay,
+}
+
+impl CalendarPart {
+    const ALL: [CalendarPart; 9] = [
+        CalendarPart::FullYear,
+        CalendarPart::ShortYear,
+        CalendarPart::PaddedYear,
+        CalendarPart::Month,
+        CalendarPart::PaddedMonth,
+        CalendarPart::Week,
+        CalendarPart::PaddedWeek,
+        CalendarPart::Day,
+        CalendarPart::PaddedDay,
+    ];
+
+    fn placeholder(self) -> &'static str {
+        match self {
+            CalendarPart::FullYear => "{yyyy}",
+            CalendarPart::ShortYear => "{yy}",
+            CalendarPart::PaddedYear => "{0y}",
+            CalendarPart::Month => "{mm}",
+            CalendarPart::PaddedMonth => "{0m}",
+            CalendarPart::Week => "{ww}",
+            CalendarPart::PaddedWeek => "{0w}",
+            CalendarPart::Day => "{dd}",
+            CalendarPart::PaddedDay => "{0d}",
+        }
+    }
+
+    /// The regex capture group, since group names cannot start with a digit.
+    fn group(self) -> &'static str {
+        match self {
+            CalendarPart::FullYear => "yyyy",
+            CalendarPart::ShortYear => "yy",
+            CalendarPart::PaddedYear => "y0",
+            CalendarPart::Month => "mm",
+            CalendarPart::PaddedMonth => "m0",
+            CalendarPart::Week => "ww",
+            CalendarPart::PaddedWeek => "w0",
+            CalendarPart::Day => "dd",
+            CalendarPart::PaddedDay => "d0",
+        }
+    }
+
+    fn regex(self) -> &'static str {
+        match self {
+            CalendarPart::FullYear => r"\d{4}",
+            CalendarPart::ShortYear => r"\d{1,3}",
+            CalendarPart::PaddedYear => r"\d{2,3}",
+            CalendarPart::Month | CalendarPart::Week | CalendarPart::Day => r"\d{1,2}",
+            CalendarPart::PaddedMonth | CalendarPart::PaddedWeek | CalendarPart::PaddedDay => {
+                r"\d{2}"
+            }
+        }
+    }
+
+    fn field(self, date: &mut CalendarDate) -> &mut Option<u32> {
+        match self {
+            CalendarPart::FullYear | CalendarPart::ShortYear | CalendarPart::PaddedYear => {
+                &mut date.year
+            }
+            CalendarPart::Month | CalendarPart::PaddedMonth => &mut date.month,
+            CalendarPart::Week | CalendarPart::PaddedWeek => &mut date.week,
+            CalendarPart::Day | CalendarPart::PaddedDay => &mut date.day,
+        }
+    }
+
+    fn value(self, date: &CalendarDate) -> Option<u32> {
+        match self {
+            CalendarPart::FullYear | CalendarPart::ShortYear | CalendarPart::PaddedYear => {
+                date.year
+            }
+            CalendarPart::Month | CalendarPart::PaddedMonth => date.month,
+            CalendarPart::Week | CalendarPart::PaddedWeek => date.week,
+            CalendarPart::Day | CalendarPart::PaddedDay => date.day,
+        }
+    }
+
+    fn parse(self, value: u32, date: &mut CalendarDate) {
+        let value = match self {
+            CalendarPart::ShortYear | CalendarPart::PaddedYear => value + 2000,
+            _ => value,
+        };
+        *self.field(date) = Some(value);
+    }
+
+    fn render(self, date: &CalendarDate) -> String {
+        let value = self.value(date).unwrap_or(0);
+        match self {
+            CalendarPart::FullYear => value.to_string(),
+            CalendarPart::ShortYear => value.saturating_sub(2000).to_string(),
+            CalendarPart::PaddedYear => format!("{:02}", value.saturating_sub(2000)),
+            CalendarPart::Month | CalendarPart::Week | CalendarPart::Day => value.to_string(),
+            CalendarPart::PaddedMonth | CalendarPart::PaddedWeek | CalendarPart::PaddedDay => {
+                format!("{:02}", value)
+            }
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Version {
     pub tag: String,
-    pub major: Option<u32>,
-    pub minor: Option<u32>,
-    pub patch: Option<u32>,
+    /// Numeric components from most to least significant, e.g. `major`, `minor`, `patch`
+    /// and `build` for `1.2.3.4567`. A literal number pinned by a scoped pattern is listed
+    /// too, such as `major = 1` for `v1.{minor}.{patch}`.
+    pub components: Vec<(String, u32)>,
+    /// Calendar components of a CalVer version such as `2026.10.3`.
+    pub date: CalendarDate,
+    /// How many of `components` rank above the date, following the pattern: 1 for the
+    /// `{major}` of `{major}.{yyyy}.{micro}`, 0 when the date leads or there is none.
+    pub date_rank: usize,
+    /// Pre-release identifiers (`rc.2` in `1.2.3-rc.2`); empty for a release.
+    pub pre: Vec<Identifier>,
+    /// Build metadata (`build.45` in `1.2.3+build.45`). Ignored for precedence.
+    pub build: Option<String>,
 }
 
 impl Version {
+    /// A SemVer version; components that are `None` are left out.
     pub fn new(tag: String, major: Option<u32>, minor: Option<u32>, patch: Option<u32>) -> Self {
+        let components = [("major", major), ("minor", minor), ("patch", patch)]
+            .into_iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value?)))
+            .collect();
+        Self::from_components(tag, components)
+    }
+
+    pub fn from_components(tag: String, components: Vec<(String, u32)>) -> Self {
         Self {
             tag,
-            major,
-            minor,
-            patch,
+            components,
+            date: CalendarDate::default(),
+            date_rank: 0,
+            pre: Vec::new(),
+            build: None,
         }
     }
+
+    /// Sets the date, ranked below the first `date_rank` components.
+    pub fn with_date(mut self, date: CalendarDate, date_rank: usize) -> Self {
+        self.date = date;
+        self.date_rank = date_rank;
+        self
+    }
+
+    /// The value of the component named `name`, e.g. `minor`.
+    pub fn component(&self, name: &str) -> Option<u32> {
+        self.components
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| *value)
+    }
+
+    pub fn with_pre(mut self, pre: Vec<Identifier>) -> Self {
+        self.pre = pre;
+        self
+    }
+
+    pub fn with_build(mut self, build: Option<String>) -> Self {
+        self.build = build;
+        self
+    }
+
+    pub fn is_pre_release(&self) -> bool {
+        !self.pre.is_empty()
+    }
+
+    /// The pre-release as written in the version, e.g. `rc.2`.
+    pub fn pre_release(&self) -> Option<String> {
+        self.is_pre_release().then(|| join_identifiers(&self.pre))
+    }
+
+    /// Compares the release components, ignoring pre-release and build metadata: the
+    /// numeric components in order of significance, with the calendar date ranked where
+    /// the pattern puts it.
+    pub fn cmp_core(&self, other: &Self) -> std::cmp::Ordering {
+        let values = |components: &[(String, u32)]| {
+            components
+                .iter()
+                .map(|(_, value)| *value)
+                .collect::<Vec<_>>()
+        };
+        let split = |v: &Self| v.date_rank.min(v.components.len());
+        let (higher, lower) = self.components.split_at(split(self));
+        let (other_higher, other_lower) = other.components.split_at(split(other));
+        values(higher)
+            .cmp(&values(other_higher))
+            .then_with(|| self.date.cmp(&other.date))
+            .then_with(|| values(lower).cmp(&values(other_lower)))
+    }
+
+    /// Compares two versions by SemVer 2.0 precedence: numeric components first, then
+    /// pre-release identifiers, where a release outranks any of its pre-releases.
+    pub fn cmp_precedence(&self, other: &Self) -> std::cmp::Ordering {
+        use std::cmp::Ordering;
+        self.cmp_core(other)
+            .then_with(|| match (self.pre.is_empty(), other.pre.is_empty()) {
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => self.pre.cmp(&other.pre),
+            })
+    }
 }
 
-#[derive(Debug, Clone, ValueEnum)]
+fn join_identifiers(ids: &[Identifier]) -> String {
+    ids.iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// The component a version bump increments. Components below it are reset to 0.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub enum Increment {
     Major,
     Minor,
+    #[default]
     Patch,
+    /// Any other numeric placeholder of the pattern, e.g. `build` in
+    /// `{major}.{minor}.{patch}.{build}`.
+    Component(String),
+}
+
+impl Increment {
+    pub fn name(&self) -> &str {
+        match self {
+            Increment::Major => "major",
+            Increment::Minor => "minor",
+            Increment::Patch => "patch",
+            Increment::Component(name) => name,
+        }
+    }
+}
+
+impl FromStr for Increment {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "major" => Increment::Major,
+            "minor" => Increment::Minor,
+            "patch" => Increment::Patch,
+            _ if is_component_name(s) && !RESERVED_PLACEHOLDERS.contains(&s) => {
+                Increment::Component(s.to_string())
+            }
+            _ => {
+                return Err(format!(
+                    "'{}' is not a component name, expected major, minor, patch or another placeholder of the pattern",
+                    s
+                ))
+            }
+        })
+    }
+}
+
+impl std::fmt::Display for Increment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Placeholders that are not numeric components.
+const RESERVED_PLACEHOLDERS: [&str; 1] = ["pre"];
+
+/// The SemVer components, most significant first.
+const SEMVER_COMPONENTS: [&str; 3] = ["major", "minor", "patch"];
+
+fn is_component_name(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_lowercase() || c == '_')
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// The span of SemVer build metadata (`+build.45`) in `pattern`: a `{build}` that is
+/// the last placeholder and directly follows another one, as in `{patch}{build}`, or
+/// follows it after a literal `+`, as in `{patch}+{build}`. After any other separator,
+/// as in `{patch}.{build}`, `{build}` is a numeric component.
+fn build_metadata(pattern: &str) -> Option<Range<usize>> {
+    let placeholder = Regex::new(r"\{[0-9a-z_]+\}").unwrap();
+    let placeholders: Vec<_> = placeholder.find_iter(pattern).collect();
+    let [.., previous, last] = placeholders.as_slice() else {
+        return None;
+    };
+    if last.as_str() != "{build}" {
+        return None;
+    }
+    match &pattern[previous.end()..last.start()] {
+        "" => Some(last.range()),
+        "+" => Some(previous.end()..last.end()),
+        _ => None,
+    }
+}
+
+fn has_build_metadata(pattern: &str) -> bool {
+    build_metadata(pattern).is_some()
+}
+
+/// Pre-release channels from least to most mature, used when no order is configured.
+pub const DEFAULT_CHANNEL_ORDER: [&str; 3] = ["alpha", "beta", "rc"];
+
+/// Regex for a dot-separated list of SemVer identifiers (pre-release or build metadata).
+const IDENTIFIERS_RE: &str = r"[0-9A-Za-z-]+(?:\.[0-9A-Za-z-]+)*";
+
+/// Returns `pattern` with a `{pre}` placeholder so it also matches pre-release tags.
+///
+/// Patterns without `{pre}` get it appended (before `{build}` if present), which matches
+/// the `<version>-<channel>.<n>` tags that `--pre` creates for them.
+pub fn pre_release_pattern(pattern: &str) -> String {
+    if pattern.contains("{pre}") {
+        pattern.to_string()
+    } else if let Some(build) = build_metadata(pattern) {
+        format!(
+            "{}{{pre}}{}",
+            &pattern[..build.start],
+            &pattern[build.start..]
+        )
+    } else {
+        format!("{}{{pre}}", pattern)
+    }
 }
 
 impl Versioner {
     pub fn new(tags: Vec<String>, pattern: String) -> Self {
-        Self { tags, pattern }
+        Self {
+            tags,
+            pattern,
+            today: None,
+            initial: None,
+        }
+    }
+
+    /// Bumps from `initial` when no tag matches the pattern, so a new project or stream
+    /// gets its first version without tagging by hand. `initial` may be written with or
+    /// without the pattern's prefix and suffix, e.g. `v0.1.0` or `0.1.0`.
+    pub fn with_initial(mut self, initial: Option<&str>) -> Result<Self, FlophaError> {
+        let Some(initial) = initial else {
+            return Ok(self);
+        };
+        // Pinned literals read as placeholders, so `3.0.0` is the bare form of
+        // `v3.{minor}.{patch}`; they must keep their value.
+        let pattern = self.unpinned_pattern();
+        let bare = match (pattern.find('{'), pattern.rfind('}')) {
+            (Some(start), Some(end)) => pattern[start..=end].to_string(),
+            _ => pattern.clone(),
+        };
+        let bare = Versioner::new(Vec::new(), bare);
+        let invalid = || FlophaError::InvalidInitialVersion {
+            input: initial.to_string(),
+            pattern: self.pattern.clone(),
+        };
+        let version = self
+            .parse(&self.get_regex(), initial)
+            .or_else(|| bare.parse(&bare.get_regex(), initial))
+            .ok_or_else(invalid)?;
+        if self
+            .pinned()
+            .iter()
+            .any(|(name, value)| version.component(name) != Some(*value))
+        {
+            return Err(invalid());
+        }
+        let tag = self.render(&version, &version.pre);
+        self.initial = Some(Version { tag, ..version });
+        Ok(self)
+    }
+
+    /// Fills CalVer placeholders from `today` instead of the current date.
+    pub fn with_today(mut self, today: CalendarDate) -> Self {
+        self.today = Some(today);
+        self
     }
 
     pub fn last_version(&self) -> Option<Version> {
@@ -105,84 +659,427 @@ impl Versioner {
         let mut versions: Vec<Version> = self
             .tags
             .iter()
-            .filter_map(|tag| {
-                let caps = regex.captures(tag)?;
-                let major = parse_version(&caps, "major");
-                let minor = parse_version(&caps, "minor");
-                let patch = parse_version(&caps, "patch");
-                Some(Version::new(tag.to_string(), major, minor, patch))
-            })
+            .filter_map(|tag| self.parse(&regex, tag))
             .collect();
-        versions.sort_by(|a, b| {
-            a.major
-                .cmp(&b.major)
-                .then(a.minor.cmp(&b.minor))
-                .then(a.patch.cmp(&b.patch))
-        });
+        versions.sort_by(|a, b| a.cmp_precedence(b));
         versions
     }
 
+    /// Parses `tag` with `regex`, the pattern's regex.
+    fn parse(&self, regex: &Regex, tag: &str) -> Option<Version> {
+        let caps = regex.captures(tag)?;
+        let pinned = self.pinned();
+        let components = self
+            .components()
+            .into_iter()
+            .map(|name| {
+                let value = match pinned.iter().find(|(n, _)| *n == name) {
+                    Some((_, value)) => *value,
+                    None => parse_version(&caps, &name)?,
+                };
+                Some((name, value))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let pre = caps
+            .name("pre")
+            .map(|m| parse_pre_release(m.as_str()))
+            .unwrap_or_default();
+        let build = has_build_metadata(&self.pattern)
+            .then(|| caps.name("build").map(|m| m.as_str().to_string()))
+            .flatten();
+        let mut date = CalendarDate::default();
+        for part in self.calendar_parts() {
+            part.parse(parse_version(&caps, part.group())?, &mut date);
+        }
+        Some(
+            Version::from_components(tag.to_string(), components)
+                .with_date(date, self.date_rank())
+                .with_pre(pre)
+                .with_build(build),
+        )
+    }
+
     pub fn next_version(&self, increment: Increment) -> Result<Option<Version>, FlophaError> {
-        let last_version = match self.last_version() {
+        let last_version = match self.last_version().or_else(|| self.initial.clone()) {
             Some(v) => v,
             None => return Ok(None),
         };
+        if !self.calendar_parts().is_empty() {
+            return self
+                .next_calendar_version(&last_version, &increment)
+                .map(Some);
+        }
 
-        let (major, minor, patch) = match increment {
-            Increment::Major => {
-                let major = last_version
-                    .major
-                    .ok_or(FlophaError::MissingVersionComponent("major".into()))?
-                    .saturating_add(1);
-                (major, 0, 0)
-            }
-            Increment::Minor => {
-                let major = last_version
-                    .major
-                    .ok_or(FlophaError::MissingVersionComponent("major".into()))?;
-                let minor = last_version
-                    .minor
-                    .ok_or(FlophaError::MissingVersionComponent("minor".into()))?
-                    .saturating_add(1);
-                (major, minor, 0)
+        let mut next =
+            Version::from_components(String::new(), self.bump(&last_version, increment.name())?);
+        next.tag = self.render(&next, &[]);
+        Ok(Some(next))
+    }
+
+    /// Moves a CalVer version to the current date and bumps the component `increment`
+    /// names, with `patch` standing for `{micro}` when the pattern has no `{patch}`.
+    ///
+    /// A component before the date, such as the `{major}` of `{major}.{yyyy}.{micro}`, is
+    /// bumped like in any other pattern. `{micro}` and the other components after the date
+    /// start over at 0 on a new date and are bumped for another release on the same date.
+    fn next_calendar_version(
+        &self,
+        last: &Version,
+        increment: &Increment,
+    ) -> Result<Version, FlophaError> {
+        let today = self.today.clone().unwrap_or_else(CalendarDate::today);
+        let mut date = CalendarDate::default();
+        for part in self.calendar_parts() {
+            *part.field(&mut date) = part.value(&today);
+        }
+
+        let name = match increment {
+            Increment::Patch if last.component("patch").is_none() => "micro",
+            increment => increment.name(),
+        };
+        let date_at = self.date_position().unwrap_or(0);
+        let position = |name: &str| self.pattern.find(&format!("{{{}}}", name));
+        let before_date = position(name).is_some_and(|at| at < date_at);
+
+        // A tag dated ahead of the clock still counts as the current period.
+        let components = if date > last.date && !before_date {
+            // A pattern without {micro} simply moves to the new date on a patch bump.
+            if *increment != Increment::Patch && last.component(name).is_none() {
+                return Err(FlophaError::MissingVersionComponent(name.to_string()));
             }
-            Increment::Patch => {
-                let major = last_version
-                    .major
-                    .ok_or(FlophaError::MissingVersionComponent("major".into()))?;
-                let minor = last_version
-                    .minor
-                    .ok_or(FlophaError::MissingVersionComponent("minor".into()))?;
-                let patch = last_version
-                    .patch
-                    .ok_or(FlophaError::MissingVersionComponent("patch".into()))?
-                    .saturating_add(1);
-                (major, minor, patch)
+            let reset = |(name, value): &(String, u32)| {
+                let after_date = position(name).is_some_and(|at| at > date_at);
+                (name.clone(), if after_date { 0 } else { *value })
+            };
+            last.components.iter().map(reset).collect()
+        } else {
+            if date <= last.date {
+                date = last.date.clone();
             }
+            self.bump(last, name)?
+        };
+        let mut next =
+            Version::from_components(String::new(), components).with_date(date, self.date_rank());
+        next.tag = self.render(&next, &[]);
+        Ok(next)
+    }
+
+    /// Increments the component `name` of `last` and resets the ones below it to 0.
+    ///
+    /// A pre-release already sits ahead of its release, so bumping the level it was cut
+    /// for releases it (1.1.0-rc.1 --minor--> 1.1.0) instead of skipping a version.
+    fn bump(&self, last: &Version, name: &str) -> Result<Vec<(String, u32)>, FlophaError> {
+        let pinned = self.pinned();
+        if let Some((component, value)) = pinned.iter().find(|(n, _)| n == name) {
+            return Err(FlophaError::PinnedVersionComponent {
+                component: component.clone(),
+                value: *value,
+                pattern: self.pattern.clone(),
+            });
+        }
+        let index = last
+            .components
+            .iter()
+            .position(|(n, _)| n == name)
+            .ok_or_else(|| FlophaError::MissingVersionComponent(name.to_string()))?;
+        let (higher, lower) = last.components.split_at(index + 1);
+        let is_pinned = |name: &str| pinned.iter().any(|(n, _)| n == name);
+        let releases_pre = last.is_pre_release()
+            && lower
+                .iter()
+                .all(|(name, value)| *value == 0 || is_pinned(name));
+
+        let mut components = higher.to_vec();
+        if !releases_pre {
+            components[index].1 = components[index].1.saturating_add(1);
+        }
+        components.extend(
+            lower
+                .iter()
+                .map(|(name, value)| (name.clone(), if is_pinned(name) { *value } else { 0 })),
+        );
+        Ok(components)
+    }
+
+    /// Returns the next `<channel>.<n>` pre-release of `base`, continuing the counter of
+    /// any pre-releases of the same version on that channel among this versioner's tags.
+    ///
+    /// A pre-release newer than the latest stable version (say `2.0.0-alpha.4`) is still in
+    /// flight: its version is kept as the base unless `base` is higher, so moving on to the
+    /// next channel yields `2.0.0-beta.1` instead of recomputing the version. Moving to a
+    /// channel that comes earlier in `channel_order` than the one in flight is refused;
+    /// channels missing from `channel_order` are not ordered.
+    pub fn next_pre_release(
+        &self,
+        base: &Version,
+        channel: &str,
+        channel_order: &[String],
+    ) -> Result<Version, FlophaError> {
+        let versions = self.sorted_versions();
+        let channel_id = Identifier::parse(channel);
+        let rank = |id: Option<&Identifier>| {
+            id.and_then(|id| {
+                channel_order
+                    .iter()
+                    .position(|c| Identifier::parse(c) == *id)
+            })
         };
 
-        let tag = self
-            .pattern
-            .replace("{major}", &major.to_string())
-            .replace("{minor}", &minor.to_string())
-            .replace("{patch}", &patch.to_string());
+        let stable = versions.iter().rfind(|v| !v.is_pre_release());
+        let in_flight: Vec<&Version> = versions
+            .iter()
+            .filter(|v| v.is_pre_release())
+            .filter(|v| stable.map_or(true, |s| v.cmp_precedence(s).is_gt()))
+            .collect();
 
-        Ok(Some(Version::new(
+        let mut core = base;
+        if let Some(latest) = in_flight.iter().max_by(|a, b| a.cmp_core(b)) {
+            if latest.cmp_core(core).is_ge() {
+                core = latest;
+                let current = in_flight
+                    .iter()
+                    .filter(|v| v.cmp_core(latest).is_eq())
+                    .filter_map(|v| rank(v.pre.first()).map(|r| (r, *v)))
+                    .max_by_key(|(r, _)| *r);
+                if let (Some(requested), Some((current_rank, current))) =
+                    (rank(Some(&channel_id)), current)
+                {
+                    if requested < current_rank {
+                        return Err(FlophaError::ChannelRegression {
+                            requested: channel.to_string(),
+                            current: current.pre[0].to_string(),
+                            tag: current.tag.clone(),
+                        });
+                    }
+                }
+            }
+        }
+
+        let max_pre = versions
+            .iter()
+            .filter(|v| v.cmp_core(core).is_eq())
+            .filter_map(|v| match v.pre.as_slice() {
+                [id, Identifier::Numeric(n)] if *id == channel_id => Some(*n),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0);
+        let pre = vec![channel_id, Identifier::Numeric(max_pre.saturating_add(1))];
+        let tag = self.render(core, &pre);
+        Ok(Version {
             tag,
-            Some(major),
-            Some(minor),
-            Some(patch),
-        )))
+            pre,
+            build: None,
+            ..core.clone()
+        })
+    }
+
+    /// Returns the release that `pre_release` leads up to, e.g. `v2.4.0` for `v2.4.0-rc.3`.
+    pub fn release_of(&self, pre_release: &Version) -> Version {
+        Version {
+            tag: self.render(pre_release, &[]),
+            pre: Vec::new(),
+            build: None,
+            ..pre_release.clone()
+        }
+    }
+
+    /// The version without the pattern's prefix or suffix, e.g. `1.4.0-rc.1` for
+    /// `v1.4.0-rc.1` or `2026.10.3` for `desktop@2026.10.3`.
+    pub fn bare_version(&self, version: &Version) -> String {
+        let pattern = self.unpinned_pattern();
+        let regex = Versioner::new(Vec::new(), pre_release_pattern(&pattern)).get_regex();
+        let Some(caps) = regex.captures(&version.tag) else {
+            return version.tag.clone();
+        };
+        let spans: Vec<_> = caps.iter().skip(1).flatten().map(|m| m.range()).collect();
+        let start = spans.iter().map(|span| span.start).min().unwrap_or(0);
+        let end = spans.iter().map(|span| span.end).max().unwrap_or(0);
+        version.tag[start..end].to_string()
+    }
+
+    /// The `(component, value)` pairs pinned by the literal numbers of a scoped pattern,
+    /// e.g. `major = 1` for `v1.{minor}.{patch}` or `minor = 0` for `v{major}.0.{patch}`.
+    ///
+    /// The literals are named by their position in a `major.minor.patch` core, so patterns
+    /// whose placeholders are out of that order pin nothing.
+    fn pinned(&self) -> Vec<(String, u32)> {
+        let Some(core) = self.pinned_core() else {
+            return Vec::new();
+        };
+        self.pattern[core]
+            .split('.')
+            .zip(SEMVER_COMPONENTS)
+            .filter_map(|(slot, name)| Some((name.to_string(), slot.parse().ok()?)))
+            .collect()
+    }
+
+    /// The pattern with its pinned literals turned back into placeholders, so that
+    /// `v1.{minor}.{patch}` reads as `v{major}.{minor}.{patch}`.
+    fn unpinned_pattern(&self) -> String {
+        let Some(core) = self.pinned_core() else {
+            return self.pattern.clone();
+        };
+        let slots = self.pattern[core.clone()]
+            .split('.')
+            .enumerate()
+            .map(|(i, slot)| match SEMVER_COMPONENTS.get(i) {
+                Some(name) if !slot.starts_with('{') => format!("{{{name}}}"),
+                _ => slot.to_string(),
+            });
+        format!(
+            "{}{}{}",
+            &self.pattern[..core.start],
+            slots.collect::<Vec<_>>().join("."),
+            &self.pattern[core.end..]
+        )
+    }
+
+    /// The span of the dot-separated version core holding pinned literals, such as
+    /// `1.{minor}.{patch}` in `v1.{minor}.{patch}`.
+    fn pinned_core(&self) -> Option<Range<usize>> {
+        let core =
+            Regex::new(r"(?:\d+|\{[a-z_][a-z0-9_]*\})(?:\.(?:\d+|\{[a-z_][a-z0-9_]*\}))+").unwrap();
+        let found = core
+            .find_iter(&self.pattern)
+            .find(|m| m.as_str().contains('{'))?;
+        let mut pins = false;
+        for (slot, name) in found.as_str().split('.').zip(SEMVER_COMPONENTS) {
+            if !slot.starts_with('{') {
+                slot.parse::<u32>().ok()?;
+                pins = true;
+            } else if slot != format!("{{{name}}}") {
+                return None;
+            }
+        }
+        pins.then(|| found.range())
+    }
+
+    /// Where the first CalVer placeholder of the pattern starts.
+    fn date_position(&self) -> Option<usize> {
+        self.calendar_parts()
+            .iter()
+            .filter_map(|part| self.pattern.find(part.placeholder()))
+            .min()
+    }
+
+    /// How many numeric components come before the date in the pattern.
+    fn date_rank(&self) -> usize {
+        let Some(date_at) = self.date_position() else {
+            return 0;
+        };
+        self.components()
+            .iter()
+            .filter(|name| {
+                self.pattern
+                    .find(&format!("{{{}}}", name))
+                    .is_some_and(|at| at < date_at)
+            })
+            .count()
+    }
+
+    /// The CalVer placeholders used by the pattern.
+    fn calendar_parts(&self) -> Vec<CalendarPart> {
+        CalendarPart::ALL
+            .into_iter()
+            .filter(|part| self.pattern.contains(part.placeholder()))
+            .collect()
+    }
+
+    /// Fills the pattern's placeholders from `version`. A pre-release is appended as
+    /// `-<pre>` when the pattern has no `{pre}` placeholder of its own.
+    fn render(&self, version: &Version, pre: &[Identifier]) -> String {
+        let pre = if pre.is_empty() {
+            String::new()
+        } else {
+            format!("-{}", join_identifiers(pre))
+        };
+        let mut tag = self.pattern.clone();
+        if let Some(build) = build_metadata(&tag) {
+            tag.replace_range(build, "");
+        }
+        for name in self.components() {
+            let value = version.component(&name).unwrap_or(0);
+            tag = tag.replace(&format!("{{{}}}", name), &value.to_string());
+        }
+        for part in self.calendar_parts() {
+            tag = tag.replace(part.placeholder(), &part.render(&version.date));
+        }
+        if tag.contains("{pre}") {
+            tag = tag.replace("{pre}", &pre);
+        } else {
+            tag.push_str(&pre);
+        }
+        tag
+    }
+
+    /// The numeric placeholders of the pattern from most to least significant: in the
+    /// order they appear, except that `major`, `minor` and `patch` always rank in that
+    /// order, so `v{patch}.{minor}.{major}` still sorts by major first.
+    fn components(&self) -> Vec<String> {
+        let placeholder = Regex::new(r"\{([a-z_][a-z0-9_]*)\}").unwrap();
+        let mut names: Vec<String> = Vec::new();
+        for caps in placeholder.captures_iter(&self.pattern) {
+            let name = &caps[1];
+            let reserved = RESERVED_PLACEHOLDERS.contains(&name)
+                || (name == "build" && has_build_metadata(&self.pattern))
+                || CalendarPart::ALL
+                    .iter()
+                    .any(|part| part.placeholder() == &caps[0]);
+            if !reserved && !names.iter().any(|n| n == name) {
+                names.push(name.to_string());
+            }
+        }
+
+        let pinned = self.pinned();
+        if !pinned.is_empty() {
+            let at = names
+                .iter()
+                .position(|name| SEMVER_COMPONENTS.contains(&name.as_str()))
+                .unwrap_or(names.len());
+            names.splice(at..at, pinned.into_iter().map(|(name, _)| name));
+        }
+
+        let mut ordered = SEMVER_COMPONENTS
+            .iter()
+            .filter(|name| names.iter().any(|n| n == *name))
+            .map(|name| name.to_string());
+        names
+            .iter()
+            .map(|name| {
+                if SEMVER_COMPONENTS.contains(&name.as_str()) {
+                    ordered.next().unwrap()
+                } else {
+                    name.clone()
+                }
+            })
+            .collect()
     }
 
     fn get_regex(&self) -> Regex {
-        let mut expr = regex::escape(&self.pattern)
-            .replace("\\{major\\}", "{major}")
-            .replace("\\{minor\\}", "{minor}")
-            .replace("\\{patch\\}", "{patch}")
-            .replace("{major}", "(?P<major>\\d+)")
-            .replace("{minor}", "(?P<minor>\\d+)")
-            .replace("{patch}", "(?P<patch>\\d+)");
+        // The regex supplies the `+` of build metadata, so drop a literal one.
+        let mut pattern = self.pattern.clone();
+        let build = build_metadata(&pattern);
+        if let Some(build) = build.clone() {
+            pattern.replace_range(build, "{build}");
+        }
+        let mut expr = regex::escape(&pattern)
+            .replace("\\{pre\\}", &format!("(?:-(?P<pre>{}))?", IDENTIFIERS_RE));
+        if build.is_some() {
+            expr = expr.replace(
+                "\\{build\\}",
+                &format!("(?:\\+(?P<build>{}))?", IDENTIFIERS_RE),
+            );
+        }
+        for name in self.components() {
+            expr = expr.replace(&format!("\\{{{}\\}}", name), &format!("(?P<{}>\\d+)", name));
+        }
+        for part in self.calendar_parts() {
+            expr = expr.replace(
+                &regex::escape(part.placeholder()),
+                &format!("(?P<{}>{})", part.group(), part.regex()),
+            );
+        }
         // Add ^ and $ to match the whole string
         expr = format!("^{}$", expr);
         Regex::new(&expr).unwrap()
@@ -248,14 +1145,24 @@ mod tests {
         let last_version = versioner.last_version();
         assert_eq!(
             last_version,
-            Some(Version::new("v1.1.0".to_string(), None, Some(1), Some(0)))
+            Some(Version::new(
+                "v1.1.0".to_string(),
+                Some(1),
+                Some(1),
+                Some(0)
+            ))
         );
 
         let versioner = Versioner::new(tags, "v{major}.0.{patch}".to_string());
         let last_version = versioner.last_version();
         assert_eq!(
             last_version,
-            Some(Version::new("v2.0.0".to_string(), Some(2), None, Some(0)))
+            Some(Version::new(
+                "v2.0.0".to_string(),
+                Some(2),
+                Some(0),
+                Some(0)
+            ))
         );
     }
 
@@ -355,7 +1262,467 @@ mod tests {
             vec!["v1.0.0".to_string(), "v1.0.1".to_string()],
             "v1.{minor}.{patch}".to_string(),
         );
-        assert!(versioner.next_version(Increment::Major).is_err());
+        assert!(matches!(
+            versioner.next_version(Increment::Major),
+            Err(FlophaError::PinnedVersionComponent { component, value: 1, .. }) if component == "major"
+        ));
+    }
+
+    #[test]
+    fn test_scoped_pattern_bumps_within_pinned_line() {
+        let versioner = Versioner::new(
+            vec![
+                "v1.8.3".to_string(),
+                "v1.8.4".to_string(),
+                "v2.1.0".to_string(),
+            ],
+            "v1.{minor}.{patch}".to_string(),
+        );
+        let next = versioner.next_version(Increment::Minor).unwrap().unwrap();
+        assert_eq!(next.tag, "v1.9.0");
+        assert_eq!(next.component("major"), Some(1));
+        assert_eq!(versioner.bare_version(&next), "1.9.0");
+
+        let versioner = Versioner::new(
+            vec!["v3.0.7".to_string(), "v3.1.0".to_string()],
+            "v{major}.0.{patch}".to_string(),
+        );
+        let next = versioner.next_version(Increment::Major).unwrap().unwrap();
+        assert_eq!(next.tag, "v4.0.0");
+        assert!(matches!(
+            versioner.next_version(Increment::Minor),
+            Err(FlophaError::PinnedVersionComponent { component, value: 0, .. }) if component == "minor"
+        ));
+    }
+
+    #[test]
+    fn test_literal_numbers_out_of_semver_order_are_not_pinned() {
+        let versioner = Versioner::new(Vec::new(), "{patch}.{minor}.1".to_string());
+        assert!(versioner.pinned().is_empty());
+        assert_eq!(versioner.unpinned_pattern(), "{patch}.{minor}.1");
+    }
+
+    #[test]
+    fn test_four_part_version_bumps_any_component() {
+        let versioner = Versioner::new(
+            vec![
+                "1.2.3.4567".to_string(),
+                "1.2.4.1".to_string(),
+                "1.2.3.4568".to_string(),
+            ],
+            "{major}.{minor}.{patch}.{build}".to_string(),
+        );
+        let last = versioner.last_version().unwrap();
+        assert_eq!(last.tag, "1.2.4.1");
+        assert_eq!(last.component("build"), Some(1));
+        assert_eq!(last.build, None);
+
+        let build = Increment::from_str("build").unwrap();
+        assert_eq!(build, Increment::Component("build".to_string()));
+        let next = versioner.next_version(build).unwrap().unwrap();
+        assert_eq!(next.tag, "1.2.4.2");
+        let next = versioner.next_version(Increment::Patch).unwrap().unwrap();
+        assert_eq!(next.tag, "1.2.5.0");
+        let next = versioner.next_version(Increment::Minor).unwrap().unwrap();
+        assert_eq!(next.tag, "1.3.0.0");
+        assert_eq!(
+            pre_release_pattern("{major}.{minor}.{patch}.{build}"),
+            "{major}.{minor}.{patch}.{build}{pre}"
+        );
+    }
+
+    #[test]
+    fn test_increment_of_unknown_component_is_an_error() {
+        let versioner = Versioner::new(
+            vec!["v1.0.0".to_string()],
+            "v{major}.{minor}.{patch}".to_string(),
+        );
+        let result = versioner.next_version(Increment::Component("build".to_string()));
+        assert!(matches!(
+            result,
+            Err(FlophaError::MissingVersionComponent(ref c)) if c == "build"
+        ));
+        assert!(Increment::from_str("pre").is_err());
+        assert!(Increment::from_str("Build!").is_err());
+    }
+
+    #[test]
+    fn test_next_version_bumps_from_initial_without_tags() {
+        let pattern = "v{major}.{minor}.{patch}".to_string();
+        let versioner = Versioner::new(vec![], pattern.clone())
+            .with_initial(Some("0.0.0"))
+            .unwrap();
+        assert_eq!(versioner.last_version(), None);
+        let next = versioner.next_version(Increment::Minor).unwrap().unwrap();
+        assert_eq!(next.tag, "v0.1.0");
+
+        let versioner = Versioner::new(vec!["v1.2.0".to_string()], pattern.clone())
+            .with_initial(Some("v0.0.0"))
+            .unwrap();
+        let next = versioner.next_version(Increment::Minor).unwrap().unwrap();
+        assert_eq!(next.tag, "v1.3.0");
+
+        let result = Versioner::new(vec![], pattern).with_initial(Some("0.1"));
+        assert!(matches!(
+            result,
+            Err(FlophaError::InvalidInitialVersion { ref input, .. }) if input == "0.1"
+        ));
+    }
+
+    #[test]
+    fn test_initial_version_of_scoped_pattern() {
+        let pattern = "v3.{minor}.{patch}".to_string();
+        for initial in ["3.0.0", "v3.0.0"] {
+            let versioner = Versioner::new(vec![], pattern.clone())
+                .with_initial(Some(initial))
+                .unwrap();
+            let next = versioner.next_version(Increment::Minor).unwrap().unwrap();
+            assert_eq!(next.tag, "v3.1.0");
+        }
+
+        for initial in ["0.0", "2.0.0"] {
+            let result = Versioner::new(vec![], pattern.clone()).with_initial(Some(initial));
+            assert!(matches!(
+                result,
+                Err(FlophaError::InvalidInitialVersion { ref input, .. }) if input == initial
+            ));
+        }
+    }
+
+    // ── SemVer pre-release / build metadata tests ────────────────────────────
+
+    #[test]
+    fn test_parses_pre_release_and_build_metadata() {
+        let versioner = Versioner::new(
+            vec!["v1.2.3-rc.2+build.45".to_string()],
+            "v{major}.{minor}.{patch}{pre}{build}".to_string(),
+        );
+        let expected = Version::new(
+            "v1.2.3-rc.2+build.45".to_string(),
+            Some(1),
+            Some(2),
+            Some(3),
+        )
+        .with_pre(vec![
+            Identifier::AlphaNumeric("rc".to_string()),
+            Identifier::Numeric(2),
+        ])
+        .with_build(Some("build.45".to_string()));
+        assert_eq!(versioner.last_version(), Some(expected));
+    }
+
+    #[test]
+    fn test_build_metadata_after_literal_plus() {
+        let versioner = Versioner::new(
+            tags(&["v1.2.3.7+build.45", "v1.2.3.6"]),
+            "v{major}.{minor}.{patch}.{rev}+{build}".to_string(),
+        );
+        let last = versioner.last_version().unwrap();
+        assert_eq!(last.component("rev"), Some(7));
+        assert_eq!(last.build.as_deref(), Some("build.45"));
+
+        let next = versioner
+            .next_version(Increment::Component("rev".to_string()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(next.tag, "v1.2.3.8");
+        assert_eq!(
+            pre_release_pattern("v{major}.{minor}.{patch}+{build}"),
+            "v{major}.{minor}.{patch}{pre}+{build}"
+        );
+    }
+
+    #[test]
+    fn test_pattern_without_pre_placeholder_ignores_pre_releases() {
+        let versioner = Versioner::new(
+            vec!["v1.0.0".to_string(), "v1.1.0-rc.1".to_string()],
+            "v{major}.{minor}.{patch}".to_string(),
+        );
+        assert_eq!(versioner.last_version().unwrap().tag, "v1.0.0");
+    }
+
+    #[test]
+    fn test_semver_precedence() {
+        // Example ordering from the SemVer 2.0 specification, shuffled.
+        let ordered = [
+            "1.0.0-alpha",
+            "1.0.0-alpha.1",
+            "1.0.0-alpha.beta",
+            "1.0.0-beta",
+            "1.0.0-beta.2",
+            "1.0.0-beta.11",
+            "1.0.0-rc.1",
+            "1.0.0",
+            "1.0.1-rc.1",
+        ];
+        let mut tags: Vec<String> = ordered.iter().map(|t| t.to_string()).collect();
+        tags.reverse();
+        tags.swap(1, 5);
+        let versioner = Versioner::new(tags, "{major}.{minor}.{patch}{pre}{build}".to_string());
+        let sorted: Vec<String> = versioner
+            .all_versions()
+            .into_iter()
+            .map(|v| v.tag)
+            .collect();
+        assert_eq!(sorted, ordered);
+    }
+
+    #[test]
+    fn test_next_version_releases_pre_release() {
+        let versioner = Versioner::new(
+            vec!["v1.0.0".to_string(), "v1.1.0-rc.2".to_string()],
+            "v{major}.{minor}.{patch}{pre}".to_string(),
+        );
+        let next = versioner.next_version(Increment::Minor).unwrap().unwrap();
+        assert_eq!(next.tag, "v1.1.0");
+        let next = versioner.next_version(Increment::Patch).unwrap().unwrap();
+        assert_eq!(next.tag, "v1.1.0");
+        let next = versioner.next_version(Increment::Major).unwrap().unwrap();
+        assert_eq!(next.tag, "v2.0.0");
+    }
+
+    #[test]
+    fn test_next_pre_release_continues_channel_counter() {
+        let versioner = Versioner::new(
+            vec![
+                "v1.1.0-rc.1".to_string(),
+                "v1.1.0-rc.2+build.7".to_string(),
+                "v1.1.0-beta.5".to_string(),
+                "v1.0.0-rc.9".to_string(),
+            ],
+            pre_release_pattern("v{major}.{minor}.{patch}{build}"),
+        );
+        let base = Version::new("v1.1.0".to_string(), Some(1), Some(1), Some(0));
+        let next = versioner.next_pre_release(&base, "rc", &[]).unwrap();
+        assert_eq!(next.tag, "v1.1.0-rc.3");
+        let next = versioner.next_pre_release(&base, "alpha", &[]).unwrap();
+        assert_eq!(next.tag, "v1.1.0-alpha.1");
+    }
+
+    fn channel_order() -> Vec<String> {
+        DEFAULT_CHANNEL_ORDER
+            .iter()
+            .map(|c| c.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_next_pre_release_keeps_in_flight_base_when_moving_up_a_channel() {
+        let versioner = Versioner::new(
+            vec![
+                "v1.4.2".to_string(),
+                "v2.0.0-alpha.3".to_string(),
+                "v2.0.0-alpha.4".to_string(),
+            ],
+            pre_release_pattern("v{major}.{minor}.{patch}"),
+        );
+        // A plain patch bump from the last stable version would give v1.4.3.
+        let base = Version::new("v1.4.3".to_string(), Some(1), Some(4), Some(3));
+        let next = versioner
+            .next_pre_release(&base, "beta", &channel_order())
+            .unwrap();
+        assert_eq!(next.tag, "v2.0.0-beta.1");
+        let next = versioner
+            .next_pre_release(&base, "alpha", &channel_order())
+            .unwrap();
+        assert_eq!(next.tag, "v2.0.0-alpha.5");
+    }
+
+    #[test]
+    fn test_next_pre_release_refuses_to_move_back_a_channel() {
+        let versioner = Versioner::new(
+            vec!["v2.0.0-alpha.4".to_string(), "v2.0.0-rc.1".to_string()],
+            pre_release_pattern("v{major}.{minor}.{patch}"),
+        );
+        let base = Version::new("v2.0.0".to_string(), Some(2), Some(0), Some(0));
+        let result = versioner.next_pre_release(&base, "beta", &channel_order());
+        assert!(matches!(
+            result,
+            Err(FlophaError::ChannelRegression { ref current, ref tag, .. })
+                if current == "rc" && tag == "v2.0.0-rc.1"
+        ));
+
+        // Channels outside the order are not ranked.
+        let next = versioner
+            .next_pre_release(&base, "nightly", &channel_order())
+            .unwrap();
+        assert_eq!(next.tag, "v2.0.0-nightly.1");
+    }
+
+    #[test]
+    fn test_next_pre_release_starts_fresh_above_released_line() {
+        let versioner = Versioner::new(
+            vec![
+                "v2.0.0-rc.2".to_string(),
+                "v2.0.0".to_string(),
+                "v2.1.0-rc.1".to_string(),
+            ],
+            pre_release_pattern("v{major}.{minor}.{patch}"),
+        );
+        let base = Version::new("v3.0.0".to_string(), Some(3), Some(0), Some(0));
+        let next = versioner
+            .next_pre_release(&base, "alpha", &channel_order())
+            .unwrap();
+        assert_eq!(next.tag, "v3.0.0-alpha.1");
+    }
+
+    // ── CalVer tests ─────────────────────────────────────────────────────────
+
+    fn tags(tags: &[&str]) -> Vec<String> {
+        tags.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn test_calendar_date_from_timestamp() {
+        assert_eq!(CalendarDate::from_timestamp(0).to_iso(), "1970-01-01");
+        let date = CalendarDate::from_timestamp(1_835_481_540);
+        assert_eq!(date.to_iso(), "2028-02-29");
+        assert_eq!(date.week, Some(9));
+        assert_eq!(CalendarDate::new(2026, 10, 17).week, Some(42));
+        assert_eq!(CalendarDate::new(2028, 12, 31).week, Some(53));
+    }
+
+    #[test]
+    fn test_calver_sorts_by_date_then_micro() {
+        let versioner = Versioner::new(
+            tags(&[
+                "2026.9.12",
+                "2026.10.3",
+                "2025.12.7",
+                "2026.10.0",
+                "2026.10.1",
+            ]),
+            "{yyyy}.{mm}.{micro}".to_string(),
+        );
+        let sorted: Vec<String> = versioner
+            .all_versions()
+            .into_iter()
+            .map(|v| v.tag)
+            .collect();
+        assert_eq!(
+            sorted,
+            [
+                "2025.12.7",
+                "2026.9.12",
+                "2026.10.0",
+                "2026.10.1",
+                "2026.10.3"
+            ]
+        );
+        let last = versioner.last_version().unwrap();
+        assert_eq!(last.date.year, Some(2026));
+        assert_eq!(last.date.month, Some(10));
+        assert_eq!(last.component("micro"), Some(3));
+    }
+
+    #[test]
+    fn test_calver_sorts_components_in_pattern_order() {
+        let versioner = Versioner::new(
+            tags(&["1.2026.3", "2.2025.0", "1.2025.9"]),
+            "{major}.{yyyy}.{micro}".to_string(),
+        );
+        let sorted: Vec<String> = versioner
+            .all_versions()
+            .into_iter()
+            .map(|v| v.tag)
+            .collect();
+        assert_eq!(sorted, ["1.2025.9", "1.2026.3", "2.2025.0"]);
+    }
+
+    #[test]
+    fn test_next_calver_bumps_micro_within_the_same_period() {
+        let versioner = Versioner::new(
+            tags(&["2026.9.12", "2026.10.3"]),
+            "{yyyy}.{mm}.{micro}".to_string(),
+        )
+        .with_today(CalendarDate::new(2026, 10, 17));
+        let next = versioner.next_version(Increment::Patch).unwrap().unwrap();
+        assert_eq!(next.tag, "2026.10.4");
+        let next = versioner.next_version(Increment::Component("micro".into()));
+        assert_eq!(next.unwrap().unwrap().tag, "2026.10.4");
+
+        let versioner = Versioner::new(tags(&["26.41.5", "26.42.1"]), "{yy}.{ww}.{micro}".into())
+            .with_today(CalendarDate::new(2026, 10, 17));
+        let next = versioner.next_version(Increment::Patch).unwrap().unwrap();
+        assert_eq!(next.tag, "26.42.2");
+    }
+
+    #[test]
+    fn test_next_calver_resets_micro_on_a_new_date() {
+        let versioner = Versioner::new(
+            tags(&["mobile@26.09.4"]),
+            "mobile@{0y}.{0m}.{micro}".to_string(),
+        )
+        .with_today(CalendarDate::new(2026, 10, 17));
+        let next = versioner.next_version(Increment::Patch).unwrap().unwrap();
+        assert_eq!(next.tag, "mobile@26.10.0");
+        assert_eq!(versioner.bare_version(&next), "26.10.0");
+    }
+
+    #[test]
+    fn test_next_calver_keeps_components_before_the_date() {
+        let versioner = Versioner::new(tags(&["3.2026.7"]), "{major}.{yyyy}.{micro}".into())
+            .with_today(CalendarDate::new(2027, 1, 4));
+        let next = versioner.next_version(Increment::Patch).unwrap().unwrap();
+        assert_eq!(next.tag, "3.2027.0");
+    }
+
+    #[test]
+    fn test_next_calver_bumps_the_requested_component() {
+        let versioner = Versioner::new(tags(&["1.2026.3"]), "{major}.{yyyy}.{micro}".into());
+        let same_date = versioner
+            .with_today(CalendarDate::new(2026, 10, 17))
+            .next_version(Increment::Major);
+        assert_eq!(same_date.unwrap().unwrap().tag, "2.2026.0");
+
+        let versioner = Versioner::new(tags(&["1.2026.3"]), "{major}.{yyyy}.{micro}".into());
+        let new_date = versioner
+            .with_today(CalendarDate::new(2027, 1, 4))
+            .next_version(Increment::Major);
+        assert_eq!(new_date.unwrap().unwrap().tag, "2.2027.0");
+
+        for today in [
+            CalendarDate::new(2026, 10, 17),
+            CalendarDate::new(2027, 1, 4),
+        ] {
+            let versioner = Versioner::new(tags(&["2026.10.3"]), "{yyyy}.{mm}.{micro}".into());
+            let result = versioner.with_today(today).next_version(Increment::Major);
+            assert!(matches!(
+                result,
+                Err(FlophaError::MissingVersionComponent(ref c)) if c == "major"
+            ));
+        }
+    }
+
+    #[test]
+    fn test_next_calver_without_micro_needs_a_new_date() {
+        let versioner = Versioner::new(tags(&["v2026.10.17"]), "v{yyyy}.{0m}.{0d}".into());
+        let result = versioner
+            .with_today(CalendarDate::new(2026, 10, 17))
+            .next_version(Increment::Patch);
+        assert!(matches!(
+            result,
+            Err(FlophaError::MissingVersionComponent(ref c)) if c == "micro"
+        ));
+
+        let versioner = Versioner::new(tags(&["v2026.10.17"]), "v{yyyy}.{0m}.{0d}".into())
+            .with_today(CalendarDate::new(2026, 11, 2));
+        let next = versioner.next_version(Increment::Patch).unwrap().unwrap();
+        assert_eq!(next.tag, "v2026.11.02");
+    }
+
+    #[test]
+    fn test_calver_pre_release() {
+        let versioner = Versioner::new(
+            tags(&["2026.10.3", "2026.10.4-rc.1"]),
+            pre_release_pattern("{yyyy}.{mm}.{micro}"),
+        )
+        .with_today(CalendarDate::new(2026, 10, 17));
+        let next = versioner.next_version(Increment::Patch).unwrap().unwrap();
+        assert_eq!(next.tag, "2026.10.4");
+        let next = versioner.next_pre_release(&next, "rc", &[]).unwrap();
+        assert_eq!(next.tag, "2026.10.4-rc.2");
+        assert_eq!(versioner.release_of(&next).tag, "2026.10.4");
     }
 
     // ── bump-rule / auto-detection tests ─────────────────────────────────────
@@ -368,7 +1735,7 @@ mod tests {
     fn test_breaking_change_footer_is_major() {
         let msgs = vec!["fix: something\n\nBREAKING CHANGE: old API removed".to_string()];
         assert!(matches!(
-            detect_increment(&msgs, &cc_rules()),
+            detect_increment(&msgs, &cc_rules()).increment,
             Increment::Major
         ));
     }
@@ -377,7 +1744,7 @@ mod tests {
     fn test_breaking_change_dash_is_major() {
         let msgs = vec!["fix: something\n\nBREAKING-CHANGE: old API removed".to_string()];
         assert!(matches!(
-            detect_increment(&msgs, &cc_rules()),
+            detect_increment(&msgs, &cc_rules()).increment,
             Increment::Major
         ));
     }
@@ -386,7 +1753,7 @@ mod tests {
     fn test_bang_after_type_is_major() {
         let msgs = vec!["feat!: redesign everything".to_string()];
         assert!(matches!(
-            detect_increment(&msgs, &cc_rules()),
+            detect_increment(&msgs, &cc_rules()).increment,
             Increment::Major
         ));
     }
@@ -395,7 +1762,7 @@ mod tests {
     fn test_bang_with_scope_is_major() {
         let msgs = vec!["feat(api)!: remove endpoint".to_string()];
         assert!(matches!(
-            detect_increment(&msgs, &cc_rules()),
+            detect_increment(&msgs, &cc_rules()).increment,
             Increment::Major
         ));
     }
@@ -405,7 +1772,7 @@ mod tests {
         // `feat!` with no trailing colon is not a valid CC breaking change
         let msgs = vec!["feat! redesign everything".to_string()];
         assert!(!matches!(
-            detect_increment(&msgs, &cc_rules()),
+            detect_increment(&msgs, &cc_rules()).increment,
             Increment::Major
         ));
     }
@@ -417,7 +1784,7 @@ mod tests {
             "feat: add new command".to_string(),
         ];
         assert!(matches!(
-            detect_increment(&msgs, &cc_rules()),
+            detect_increment(&msgs, &cc_rules()).increment,
             Increment::Minor
         ));
     }
@@ -426,7 +1793,7 @@ mod tests {
     fn test_feat_with_scope_is_minor() {
         let msgs = vec!["feat(cli): add --auto flag".to_string()];
         assert!(matches!(
-            detect_increment(&msgs, &cc_rules()),
+            detect_increment(&msgs, &cc_rules()).increment,
             Increment::Minor
         ));
     }
@@ -435,7 +1802,7 @@ mod tests {
     fn test_fix_only_is_patch() {
         let msgs = vec!["fix: typo".to_string(), "chore: update deps".to_string()];
         assert!(matches!(
-            detect_increment(&msgs, &cc_rules()),
+            detect_increment(&msgs, &cc_rules()).increment,
             Increment::Patch
         ));
     }
@@ -443,7 +1810,7 @@ mod tests {
     #[test]
     fn test_empty_messages_is_patch() {
         assert!(matches!(
-            detect_increment(&[], &cc_rules()),
+            detect_increment(&[], &cc_rules()).increment,
             Increment::Patch
         ));
     }
@@ -456,13 +1823,22 @@ mod tests {
         ];
         // "feat:" would be minor under defaults but there's no matching rule here → patch
         let msgs = vec!["feat: something".to_string()];
-        assert!(matches!(detect_increment(&msgs, &rules), Increment::Patch));
+        assert!(matches!(
+            detect_increment(&msgs, &rules).increment,
+            Increment::Patch
+        ));
 
         let msgs = vec!["MINOR: add thing".to_string()];
-        assert!(matches!(detect_increment(&msgs, &rules), Increment::Minor));
+        assert!(matches!(
+            detect_increment(&msgs, &rules).increment,
+            Increment::Minor
+        ));
 
         let msgs = vec!["MAJOR: big change".to_string()];
-        assert!(matches!(detect_increment(&msgs, &rules), Increment::Major));
+        assert!(matches!(
+            detect_increment(&msgs, &rules).increment,
+            Increment::Major
+        ));
     }
 
     #[test]
@@ -473,6 +1849,32 @@ mod tests {
         ];
         // Both match; major should win and return immediately
         let msgs = vec!["breaking feature change".to_string()];
-        assert!(matches!(detect_increment(&msgs, &rules), Increment::Major));
+        assert!(matches!(
+            detect_increment(&msgs, &rules).increment,
+            Increment::Major
+        ));
+    }
+
+    #[test]
+    fn test_detect_increment_reports_each_commit() {
+        let msgs = vec![
+            "fix: typo".to_string(),
+            "feat(api): add endpoint\n\nBREAKING CHANGE: removes v1".to_string(),
+            "feat: search".to_string(),
+        ];
+        let report = detect_increment(&msgs, &cc_rules());
+        assert_eq!(report.increment, Increment::Major);
+        assert_eq!(report.commits.len(), 3);
+        assert_eq!(report.commits[0].subject, "fix: typo");
+        assert_eq!(report.commits[0].rule, None);
+        assert_eq!(report.commits[1].subject, "feat(api): add endpoint");
+        assert_eq!(
+            report.commits[1].rule,
+            Some(("BREAKING[- ]CHANGE".to_string(), Increment::Major))
+        );
+        assert_eq!(
+            report.commits[2].rule,
+            Some((r"(?m)^feat(\([^)]+\))?:".to_string(), Increment::Minor))
+        );
     }
 }